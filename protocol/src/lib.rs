@@ -0,0 +1,170 @@
+// 浏览器和中继服务器之间的消息，都用JSON文本发
+use serde::{Deserialize, Serialize};
+
+// 改了消息格式就加一，版本不同的客户端和服务器不能一起玩
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const DEFAULT_PORT: u16 = 9001;
+pub const MAX_ROOM_PLAYERS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub id: u32,
+    pub name: String,
+    pub ready: bool,
+}
+
+// 一只鸟某一时刻的状态，x是世界坐标，pos相对屏幕中线
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BirdState {
+    pub x: f64,
+    pub pos: f64,
+    pub angle: f64,
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    // 连上以后第一条必须是这个
+    Hello {
+        version: u32,
+        room: String,
+        name: String,
+    },
+    // 准备好开始下一局，房间里所有人都准备好就开始
+    Ready,
+    State {
+        state: BirdState,
+    },
+    Crashed {
+        score: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        id: u32,
+        room: String,
+        players: Vec<PlayerInfo>,
+    },
+    // 发完这个服务器就断开连接
+    Error {
+        message: String,
+    },
+    Joined {
+        player: PlayerInfo,
+    },
+    Left {
+        id: u32,
+    },
+    Ready {
+        id: u32,
+    },
+    // 大家用同一个种子生成赛道
+    Start {
+        seed: u64,
+    },
+    State {
+        id: u32,
+        state: BirdState,
+    },
+    Crashed {
+        id: u32,
+        score: u32,
+    },
+    // 所有人都撞了，(id, 得分)
+    RoundOver {
+        scores: Vec<(u32, u32)>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Serialize + for<'a> Deserialize<'a> + PartialEq + std::fmt::Debug>(msg: T) {
+        let text = serde_json::to_string(&msg).unwrap();
+        assert_eq!(serde_json::from_str::<T>(&text).unwrap(), msg);
+    }
+
+    fn state() -> BirdState {
+        BirdState {
+            x: 1234.5,
+            pos: -67.25,
+            angle: 0.5,
+            score: 8,
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        round_trip(ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            room: "room".to_string(),
+            name: "小鸟".to_string(),
+        });
+        round_trip(ClientMessage::Ready);
+        round_trip(ClientMessage::State { state: state() });
+        round_trip(ClientMessage::Crashed { score: 8 });
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        let player = PlayerInfo {
+            id: 2,
+            name: "bird".to_string(),
+            ready: true,
+        };
+        round_trip(ServerMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            id: 2,
+            room: "room".to_string(),
+            players: vec![player.clone()],
+        });
+        round_trip(ServerMessage::Error {
+            message: "Room is full".to_string(),
+        });
+        round_trip(ServerMessage::Joined { player });
+        round_trip(ServerMessage::Left { id: 2 });
+        round_trip(ServerMessage::Ready { id: 2 });
+        round_trip(ServerMessage::Start { seed: u64::MAX });
+        round_trip(ServerMessage::State {
+            id: 2,
+            state: state(),
+        });
+        round_trip(ServerMessage::Crashed { id: 2, score: 8 });
+        round_trip(ServerMessage::RoundOver {
+            scores: vec![(1, 3), (2, 8)],
+        });
+    }
+
+    // 这里失败说明改了消息格式，记得把PROTOCOL_VERSION加一，再改这个测试
+    #[test]
+    fn wire_format_matches_protocol_version() {
+        assert_eq!(PROTOCOL_VERSION, 1);
+        let hello = ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            room: "r".to_string(),
+            name: "n".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&hello).unwrap(),
+            r#"{"type":"hello","version":1,"room":"r","name":"n"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientMessage::State { state: state() }).unwrap(),
+            r#"{"type":"state","state":{"x":1234.5,"pos":-67.25,"angle":0.5,"score":8}}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ServerMessage::RoundOver {
+                scores: vec![(1, 3)]
+            })
+            .unwrap(),
+            r#"{"type":"round_over","scores":[[1,3]]}"#
+        );
+    }
+}
@@ -0,0 +1,364 @@
+// 联机对战的中继服务器，只负责分房间和转发消息，不跑游戏逻辑
+// 用法: flying-bird-relay [监听地址]，默认127.0.0.1:9001
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use flying_bird_protocol::{
+    ClientMessage, PlayerInfo, ServerMessage, DEFAULT_PORT, MAX_ROOM_PLAYERS, PROTOCOL_VERSION,
+};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio_tungstenite::tungstenite::Message;
+
+struct Peer {
+    info: PlayerInfo,
+    // 这一局还在飞
+    alive: bool,
+    score: u32,
+    tx: UnboundedSender<Message>,
+}
+
+#[derive(Default)]
+struct Room {
+    peers: Vec<Peer>,
+    playing: bool,
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+fn send(tx: &UnboundedSender<Message>, msg: &ServerMessage) {
+    // 对方断开了就算了，读的那边会处理
+    let _ = tx.send(Message::text(serde_json::to_string(msg).unwrap()));
+}
+
+impl Room {
+    fn broadcast(&self, msg: &ServerMessage, except: Option<u32>) {
+        for peer in self.peers.iter().filter(|p| Some(p.info.id) != except) {
+            send(&peer.tx, msg);
+        }
+    }
+
+    fn peer_mut(&mut self, id: u32) -> Option<&mut Peer> {
+        self.peers.iter_mut().find(|p| p.info.id == id)
+    }
+
+    // 至少两个人并且都准备好了才开始
+    fn try_start(&mut self) {
+        if self.playing || self.peers.len() < 2 || !self.peers.iter().all(|p| p.info.ready) {
+            return;
+        }
+        self.playing = true;
+        for peer in self.peers.iter_mut() {
+            peer.info.ready = false;
+            peer.alive = true;
+            peer.score = 0;
+        }
+        self.broadcast(
+            &ServerMessage::Start {
+                seed: rand::random(),
+            },
+            None,
+        );
+    }
+
+    fn try_finish(&mut self) {
+        if !self.playing || self.peers.iter().any(|p| p.alive) {
+            return;
+        }
+        self.playing = false;
+        let scores = self.peers.iter().map(|p| (p.info.id, p.score)).collect();
+        self.broadcast(&ServerMessage::RoundOver { scores }, None);
+        // 有人在上一局没结束时就准备好了
+        self.try_start();
+    }
+
+    fn handle(&mut self, id: u32, msg: ClientMessage) {
+        match msg {
+            ClientMessage::Hello { .. } => {}
+            ClientMessage::Ready => {
+                if let Some(peer) = self.peer_mut(id) {
+                    peer.info.ready = true;
+                }
+                self.broadcast(&ServerMessage::Ready { id }, None);
+                self.try_start();
+            }
+            ClientMessage::State { state } => {
+                self.broadcast(&ServerMessage::State { id, state }, Some(id));
+            }
+            ClientMessage::Crashed { score } => {
+                let Some(peer) = self.peer_mut(id).filter(|p| p.alive) else {
+                    return;
+                };
+                peer.alive = false;
+                peer.score = score;
+                self.broadcast(&ServerMessage::Crashed { id, score }, Some(id));
+                self.try_finish();
+            }
+        }
+    }
+
+    // 不让进的原因
+    fn refuse(&self, version: u32) -> Option<String> {
+        if version != PROTOCOL_VERSION {
+            return Some(format!(
+                "Protocol version {version} is not supported, server speaks {PROTOCOL_VERSION}"
+            ));
+        }
+        if self.peers.len() >= MAX_ROOM_PLAYERS {
+            return Some("Room is full".to_string());
+        }
+        None
+    }
+
+    fn leave(&mut self, id: u32) {
+        self.peers.retain(|p| p.info.id != id);
+        self.broadcast(&ServerMessage::Left { id }, None);
+        self.try_finish();
+        self.try_start();
+    }
+}
+
+async fn handle_connection(stream: TcpStream, rooms: Rooms, id: u32) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut sink, mut source) = ws.split();
+
+    // 所有发给这个连接的消息都经过这个channel
+    let (tx, mut rx) = unbounded_channel::<Message>();
+    let writer = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            if sink.send(msg).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut joined: Option<String> = None;
+    while let Some(Ok(msg)) = source.next().await {
+        let Message::Text(text) = msg else {
+            continue;
+        };
+        let Ok(msg) = serde_json::from_str::<ClientMessage>(&text) else {
+            continue;
+        };
+        let mut rooms = rooms.lock().unwrap();
+        match (&joined, msg) {
+            (
+                None,
+                ClientMessage::Hello {
+                    version,
+                    room: room_name,
+                    name,
+                },
+            ) => {
+                let room = rooms.entry(room_name.clone()).or_default();
+                if let Some(message) = room.refuse(version) {
+                    if room.peers.is_empty() {
+                        rooms.remove(&room_name);
+                    }
+                    send(&tx, &ServerMessage::Error { message });
+                    break;
+                }
+                let player = PlayerInfo {
+                    id,
+                    name,
+                    ready: false,
+                };
+                room.broadcast(
+                    &ServerMessage::Joined {
+                        player: player.clone(),
+                    },
+                    None,
+                );
+                room.peers.push(Peer {
+                    info: player,
+                    alive: false,
+                    score: 0,
+                    tx: tx.clone(),
+                });
+                send(
+                    &tx,
+                    &ServerMessage::Welcome {
+                        version: PROTOCOL_VERSION,
+                        id,
+                        room: room_name.clone(),
+                        players: room.peers.iter().map(|p| p.info.clone()).collect(),
+                    },
+                );
+                println!("#{id} joined room \"{room_name}\"");
+                joined = Some(room_name);
+            }
+            (None, _) => {
+                send(
+                    &tx,
+                    &ServerMessage::Error {
+                        message: "Expected hello".to_string(),
+                    },
+                );
+                break;
+            }
+            (Some(room_name), msg) => {
+                if let Some(room) = rooms.get_mut(room_name) {
+                    room.handle(id, msg);
+                }
+            }
+        }
+    }
+
+    if let Some(room_name) = joined {
+        let mut rooms = rooms.lock().unwrap();
+        if let Some(room) = rooms.get_mut(&room_name) {
+            room.leave(id);
+            if room.peers.is_empty() {
+                rooms.remove(&room_name);
+            }
+        }
+        println!("#{id} left room \"{room_name}\"");
+    }
+    drop(tx);
+    let _ = writer.await;
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("127.0.0.1:{DEFAULT_PORT}"));
+    let listener = TcpListener::bind(&addr).await.expect("bind failed");
+    println!("Relay listening on ws://{addr}");
+
+    let rooms = Rooms::default();
+    let mut next_id = 0;
+    loop {
+        // 文件描述符用完之类的错误过一会儿就好了，不能让整个服务器退出
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("Accept failed: {e}");
+                continue;
+            }
+        };
+        next_id += 1;
+        tokio::spawn(handle_connection(stream, rooms.clone(), next_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    use super::*;
+
+    fn join(room: &mut Room, id: u32) -> UnboundedReceiver<Message> {
+        let (tx, rx) = unbounded_channel();
+        room.peers.push(Peer {
+            info: PlayerInfo {
+                id,
+                name: format!("#{id}"),
+                ready: false,
+            },
+            alive: false,
+            score: 0,
+            tx,
+        });
+        rx
+    }
+
+    // 到现在为止收到的消息
+    fn received(rx: &mut UnboundedReceiver<Message>) -> Vec<ServerMessage> {
+        let mut messages = vec![];
+        while let Ok(Message::Text(text)) = rx.try_recv() {
+            messages.push(serde_json::from_str(&text).unwrap());
+        }
+        messages
+    }
+
+    fn started(messages: &[ServerMessage]) -> bool {
+        messages
+            .iter()
+            .any(|m| matches!(m, ServerMessage::Start { .. }))
+    }
+
+    fn round_over(messages: &[ServerMessage]) -> Option<&Vec<(u32, u32)>> {
+        messages.iter().find_map(|m| match m {
+            ServerMessage::RoundOver { scores } => Some(scores),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn starts_when_everyone_is_ready() {
+        let mut room = Room::default();
+        let mut rx1 = join(&mut room, 1);
+        let mut rx2 = join(&mut room, 2);
+
+        room.handle(1, ClientMessage::Ready);
+        assert!(!started(&received(&mut rx1)));
+        room.handle(2, ClientMessage::Ready);
+        assert!(room.playing);
+        assert!(started(&received(&mut rx1)));
+        assert!(started(&received(&mut rx2)));
+    }
+
+    #[test]
+    fn one_player_alone_does_not_start() {
+        let mut room = Room::default();
+        let mut rx = join(&mut room, 1);
+        room.handle(1, ClientMessage::Ready);
+        assert!(!room.playing);
+        assert!(!started(&received(&mut rx)));
+    }
+
+    #[test]
+    fn finishes_when_everyone_has_crashed() {
+        let mut room = Room::default();
+        let mut rx1 = join(&mut room, 1);
+        let _rx2 = join(&mut room, 2);
+        room.handle(1, ClientMessage::Ready);
+        room.handle(2, ClientMessage::Ready);
+
+        room.handle(2, ClientMessage::Crashed { score: 3 });
+        assert!(round_over(&received(&mut rx1)).is_none());
+        // 重复发的不算
+        room.handle(2, ClientMessage::Crashed { score: 5 });
+        room.handle(1, ClientMessage::Crashed { score: 7 });
+        assert!(!room.playing);
+        assert_eq!(round_over(&received(&mut rx1)), Some(&vec![(1, 7), (2, 3)]));
+    }
+
+    #[test]
+    fn leaving_mid_race() {
+        let mut room = Room::default();
+        let mut rx1 = join(&mut room, 1);
+        let _rx2 = join(&mut room, 2);
+        let _rx3 = join(&mut room, 3);
+        for id in 1..=3 {
+            room.handle(id, ClientMessage::Ready);
+        }
+        room.handle(1, ClientMessage::Crashed { score: 4 });
+        room.handle(2, ClientMessage::Crashed { score: 2 });
+        received(&mut rx1);
+
+        // 最后一个还在飞的走了，这一局就结束
+        room.leave(3);
+        let messages = received(&mut rx1);
+        assert!(messages.contains(&ServerMessage::Left { id: 3 }));
+        assert_eq!(round_over(&messages), Some(&vec![(1, 4), (2, 2)]));
+        assert!(!room.playing);
+    }
+
+    #[test]
+    fn refuses_other_versions_and_full_rooms() {
+        let mut room = Room::default();
+        assert!(room.refuse(PROTOCOL_VERSION).is_none());
+        assert!(room.refuse(PROTOCOL_VERSION + 1).is_some());
+        let _rx: Vec<_> = (1..=MAX_ROOM_PLAYERS as u32)
+            .map(|id| join(&mut room, id))
+            .collect();
+        assert!(room.refuse(PROTOCOL_VERSION).is_some());
+    }
+}
@@ -0,0 +1,117 @@
+// 皮肤的颜色是用css filter写的，这里照着规范里的矩阵算一遍，和浏览器画出来一样
+// https://www.w3.org/TR/filter-effects-1/#ShorthandEquivalents
+
+type Matrix = [[f64; 3]; 3];
+
+fn sepia(a: f64) -> Matrix {
+    let k = 1. - a.clamp(0., 1.);
+    [
+        [0.393 + 0.607 * k, 0.769 - 0.769 * k, 0.189 - 0.189 * k],
+        [0.349 - 0.349 * k, 0.686 + 0.314 * k, 0.168 - 0.168 * k],
+        [0.272 - 0.272 * k, 0.534 - 0.534 * k, 0.131 + 0.869 * k],
+    ]
+}
+
+fn saturate(s: f64) -> Matrix {
+    [
+        [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+        [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+        [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+    ]
+}
+
+fn hue_rotate(deg: f64) -> Matrix {
+    let (sin, cos) = deg.to_radians().sin_cos();
+    [
+        [
+            0.213 + cos * 0.787 - sin * 0.213,
+            0.715 - cos * 0.715 - sin * 0.715,
+            0.072 - cos * 0.072 + sin * 0.928,
+        ],
+        [
+            0.213 - cos * 0.213 + sin * 0.143,
+            0.715 + cos * 0.285 + sin * 0.140,
+            0.072 - cos * 0.072 - sin * 0.283,
+        ],
+        [
+            0.213 - cos * 0.213 - sin * 0.787,
+            0.715 - cos * 0.715 + sin * 0.715,
+            0.072 + cos * 0.928 + sin * 0.072,
+        ],
+    ]
+}
+
+fn brightness(b: f64) -> Matrix {
+    [[b, 0., 0.], [0., b, 0.], [0., 0., b]]
+}
+
+// 一串滤镜，按顺序作用，每一步之后截到0..1
+pub struct Filter(Vec<Matrix>);
+
+impl Filter {
+    // 只认皮肤里用到的几种，"none"或者认不出的就不变
+    pub fn parse(css: &str) -> Self {
+        Self(
+            css.split_whitespace()
+                .filter_map(|f| {
+                    let (name, arg) = f.strip_suffix(')')?.split_once('(')?;
+                    // 0.5和50%一样
+                    let amount = || match arg.strip_suffix('%') {
+                        Some(percent) => percent.parse().ok().map(|p: f64| p / 100.),
+                        None => arg.parse().ok(),
+                    };
+                    Some(match name {
+                        "sepia" => sepia(amount()?),
+                        "saturate" => saturate(amount()?),
+                        "hue-rotate" => hue_rotate(arg.strip_suffix("deg")?.parse().ok()?),
+                        "brightness" => brightness(amount()?),
+                        _ => return None,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub fn apply(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let mut c = rgb.map(|x| x as f64 / 255.);
+        for m in self.0.iter() {
+            c = [0, 1, 2].map(|i| (m[i][0] * c[0] + m[i][1] * c[1] + m[i][2] * c[2]).clamp(0., 1.));
+        }
+        c.map(|x| (x * 255.).round() as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn none_keeps_colors() {
+        let filter = Filter::parse("none");
+        assert_eq!(filter.apply([12, 200, 99]), [12, 200, 99]);
+    }
+
+    #[test]
+    fn brightness_scales() {
+        assert_eq!(
+            Filter::parse("brightness(0.5)").apply([200, 100, 0]),
+            [100, 50, 0]
+        );
+        assert_eq!(
+            Filter::parse("brightness(50%)").apply([200, 100, 0]),
+            [100, 50, 0]
+        );
+    }
+
+    #[test]
+    fn full_turn_of_hue_is_identity() {
+        let filter = Filter::parse("hue-rotate(360deg)");
+        assert_eq!(filter.apply([30, 140, 220]), [30, 140, 220]);
+    }
+
+    #[test]
+    fn sepia_tints_gray() {
+        let [r, g, b] = Filter::parse("sepia(1)").apply([128, 128, 128]);
+        assert!(r > g && g > b);
+    }
+}
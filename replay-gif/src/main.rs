@@ -0,0 +1,193 @@
+// 把网页上保存的回放画成gif，发到群里或者贴进bug报告
+// 用法: flying-bird-gif <回放.json> [输出.gif] [--width 像素] [--skip 每几帧取一帧] [--skin 皮肤] [--high-contrast]
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process::exit;
+
+use flying_bird_sim::render::{self, draw_background, draw_warning, draw_world};
+use flying_bird_sim::replay::{Playback, Replay};
+use flying_bird_sim::theme::{player_skins, Animator, Palette, Skin};
+use flying_bird_sim::HISTORY_LEN;
+use gif::{Encoder, Frame, Repeat};
+use raster::SkiaRenderer;
+use tiny_skia::Pixmap;
+
+mod filter;
+mod raster;
+
+// 和网页里一帧的间隔一样，毫秒
+const FRAME_MS: usize = 8;
+const DEFAULT_WIDTH: u32 = 480;
+const DEFAULT_SKIP: usize = 3;
+// gif的延时单位是10ms，太小浏览器会当成100ms
+const MIN_DELAY: u16 = 2;
+// 颜色量化的速度，1最慢最好，30最快
+const QUANTIZE_SPEED: i32 = 10;
+
+// gif的延时只能是10ms的整数倍，每帧四舍五入的误差留到下一帧，总时长才对得上
+struct Delays {
+    // 每一帧应该放多久，毫秒
+    frame_ms: usize,
+    // 到现在应该放了多久和实际写了多少
+    elapsed_ms: usize,
+    written: usize,
+}
+
+impl Delays {
+    fn new(frame_ms: usize) -> Self {
+        Self {
+            frame_ms,
+            elapsed_ms: 0,
+            written: 0,
+        }
+    }
+
+    // 下一帧的延时，10ms为单位
+    fn next(&mut self) -> u16 {
+        self.elapsed_ms += self.frame_ms;
+        let target = (self.elapsed_ms + 5) / 10;
+        let delay = target.saturating_sub(self.written).max(MIN_DELAY as usize);
+        self.written += delay;
+        delay as u16
+    }
+}
+
+struct Options {
+    input: String,
+    output: String,
+    width: u32,
+    skip: usize,
+    skin: String,
+    high_contrast: bool,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: flying-bird-gif <replay.json> [out.gif] [--width N] [--skip N] [--skin ID] [--high-contrast]"
+    );
+    exit(2)
+}
+
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+    let mut files = vec![];
+    let mut options = Options {
+        input: String::new(),
+        output: String::new(),
+        width: DEFAULT_WIDTH,
+        skip: DEFAULT_SKIP,
+        skin: String::new(),
+        high_contrast: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--width" => options.width = value().parse().unwrap_or_else(|_| usage()),
+            "--skip" => options.skip = value().parse().unwrap_or_else(|_| usage()),
+            "--skin" => options.skin = value(),
+            "--high-contrast" => options.high_contrast = true,
+            _ if arg.starts_with("--") => usage(),
+            _ => files.push(arg),
+        }
+    }
+    let mut files = files.into_iter();
+    options.input = files.next().unwrap_or_else(|| usage());
+    options.output = files
+        .next()
+        .unwrap_or_else(|| format!("{}.gif", options.input.trim_end_matches(".json")));
+    if files.next().is_some() || options.width == 0 || options.skip == 0 {
+        usage();
+    }
+    options
+}
+
+fn main() {
+    let options = parse_args();
+    let replay: Replay = fs::read_to_string(&options.input)
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("can't read {}: {e}", options.input);
+            exit(1)
+        });
+    if replay.is_empty() {
+        eprintln!("{} has no frames", options.input);
+        exit(1);
+    }
+
+    let skin = Skin::by_id(&options.skin);
+    let skins = player_skins(skin);
+    let sprites = skins.map(raster::bird_sprite);
+    let tints = skins.map(|skin| skin.tint);
+    let palette = Palette::get(options.high_contrast);
+
+    let scale = options.width as f64 / replay.w;
+    let (w, h) = (options.width, (replay.h * scale).round() as u32);
+    let (Ok(gif_w), Ok(gif_h)) = (u16::try_from(w), u16::try_from(h)) else {
+        eprintln!("{w}x{h} is too large for a gif");
+        exit(1)
+    };
+    let mut delays = Delays::new(FRAME_MS * options.skip);
+
+    let file = File::create(&options.output).unwrap_or_else(|e| {
+        eprintln!("can't create {}: {e}", options.output);
+        exit(1)
+    });
+    let mut encoder = Encoder::new(BufWriter::new(file), gif_w, gif_h, &[]).unwrap();
+    encoder.set_repeat(Repeat::Infinite).unwrap();
+
+    let mut pixmap = Pixmap::new(w, h).unwrap();
+    let mut animators: [Animator; 2] = Default::default();
+    let mut playback = Playback::default();
+    let mut written = 0;
+    for i in 0..replay.len() {
+        let world = playback.world(&replay, i);
+        // 跳过的帧也要走动画，扇翅膀的快慢才对
+        let poses = render::poses(&world, &mut animators);
+        if i % options.skip != 0 {
+            continue;
+        }
+        let mut renderer = SkiaRenderer::new(&mut pixmap, &sprites, scale);
+        draw_background(&mut renderer, &world, palette);
+        draw_world(&mut renderer, &world, &poses, tints, palette, HISTORY_LEN);
+        draw_warning(&mut renderer, &world, palette);
+        // 背景不透明，预乘过的颜色和原来一样
+        let mut frame =
+            Frame::from_rgba_speed(gif_w, gif_h, &mut pixmap.data().to_vec(), QUANTIZE_SPEED);
+        frame.delay = delays.next();
+        encoder.write_frame(&frame).unwrap();
+        written += 1;
+    }
+    println!("wrote {written} frames ({w}x{h}) to {}", options.output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn total(frame_ms: usize, frames: usize) -> (u32, Vec<u16>) {
+        let mut delays = Delays::new(frame_ms);
+        let delays: Vec<u16> = (0..frames).map(|_| delays.next()).collect();
+        (delays.iter().map(|d| *d as u32).sum(), delays)
+    }
+
+    #[test]
+    fn delays_add_up_to_the_real_duration() {
+        // 默认每3帧取一帧，24ms一帧
+        let (sum, delays) = total(24, 100);
+        assert_eq!(sum, 240);
+        assert!(delays.iter().all(|d| (2..=3).contains(d)));
+
+        let (sum, _) = total(FRAME_MS * 5, 7);
+        assert_eq!(sum, 28);
+    }
+
+    #[test]
+    fn delays_never_go_below_the_minimum() {
+        // 8ms一帧放不了那么快，只能慢一点
+        let (sum, delays) = total(FRAME_MS, 10);
+        assert!(delays.iter().all(|d| *d == MIN_DELAY));
+        assert_eq!(sum, 20);
+    }
+}
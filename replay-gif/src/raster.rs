@@ -0,0 +1,222 @@
+// 用tiny-skia在内存里画，画什么由flying_bird_sim::render决定，和网页上一样
+use std::io::Cursor;
+
+use flying_bird_sim::render::{Color, Renderer};
+use flying_bird_sim::theme::{Anim, Skin};
+use flying_bird_sim::BIRD_SIZE;
+use image_webp::WebPDecoder;
+use tiny_skia::{FilterQuality, Paint, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform};
+
+use crate::filter::Filter;
+
+const BIRD_WEBP: &[u8] = include_bytes!("../../static/bird.webp");
+
+// 按皮肤染好色的鸟
+pub fn bird_sprite(skin: &Skin) -> Pixmap {
+    let mut decoder = WebPDecoder::new(Cursor::new(BIRD_WEBP)).expect("bad bird.webp");
+    let (w, h) = decoder.dimensions();
+    let channels = if decoder.has_alpha() { 4 } else { 3 };
+    let mut buf = vec![0; w as usize * h as usize * channels];
+    decoder.read_image(&mut buf).expect("bad bird.webp");
+
+    let filter = Filter::parse(skin.filter);
+    let mut pixmap = Pixmap::new(w, h).unwrap();
+    for (src, dst) in buf.chunks(channels).zip(pixmap.pixels_mut()) {
+        let [r, g, b] = filter.apply([src[0], src[1], src[2]]);
+        let a = if channels == 4 { src[3] } else { 255 };
+        *dst = tiny_skia::ColorU8::from_rgba(r, g, b, a).premultiply();
+    }
+    pixmap
+}
+
+pub struct SkiaRenderer<'a> {
+    pixmap: &'a mut Pixmap,
+    // 两个玩家的鸟
+    sprites: &'a [Pixmap; 2],
+    // 世界坐标到图片像素
+    transform: Transform,
+}
+
+impl<'a> SkiaRenderer<'a> {
+    pub fn new(pixmap: &'a mut Pixmap, sprites: &'a [Pixmap; 2], scale: f64) -> Self {
+        Self {
+            pixmap,
+            sprites,
+            transform: Transform::from_scale(scale as f32, scale as f32),
+        }
+    }
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut c = tiny_skia::Color::from_rgba8(color.r, color.g, color.b, 255);
+    c.apply_opacity(color.a as f32);
+    let mut paint = Paint::default();
+    paint.set_color(c);
+    paint.anti_alias = true;
+    paint
+}
+
+impl Renderer for SkiaRenderer<'_> {
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        if let Some(rect) = Rect::from_xywh(x as f32, y as f32, w as f32, h as f32) {
+            self.pixmap
+                .fill_rect(rect, &paint(color), self.transform, None);
+        }
+    }
+
+    fn stroke_line(&mut self, points: impl Iterator<Item = (f64, f64)>, width: f64, color: Color) {
+        let mut pb = PathBuilder::new();
+        for (i, (x, y)) in points.enumerate() {
+            if i == 0 {
+                pb.move_to(x as f32, y as f32);
+            } else {
+                pb.line_to(x as f32, y as f32);
+            }
+        }
+        if let Some(path) = pb.finish() {
+            let stroke = Stroke {
+                width: width as f32,
+                ..Default::default()
+            };
+            self.pixmap
+                .stroke_path(&path, &paint(color), &stroke, self.transform, None);
+        }
+    }
+
+    fn draw_bird(
+        &mut self,
+        player: usize,
+        (anim, frame): (Anim, usize),
+        x: f64,
+        y: f64,
+        angle: f64,
+        alpha: f64,
+    ) {
+        let sprite = &self.sprites[player];
+        let (sx, sy, rotate) = anim.pose(frame);
+        let size = BIRD_SIZE / sprite.width() as f64;
+        let t = self
+            .transform
+            .pre_translate(x as f32, y as f32)
+            .pre_rotate(angle.to_degrees() as f32)
+            .pre_rotate(rotate.to_degrees() as f32)
+            .pre_scale(sx as f32, sy as f32)
+            .pre_translate(-BIRD_SIZE as f32 / 2., -BIRD_SIZE as f32 / 2.)
+            .pre_scale(size as f32, size as f32);
+        let paint = PixmapPaint {
+            opacity: alpha as f32,
+            quality: FilterQuality::Bilinear,
+            ..Default::default()
+        };
+        self.pixmap
+            .draw_pixmap(0, 0, sprite.as_ref(), &paint, t, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::PathBuf;
+
+    use flying_bird_sim::bot::Autopilot;
+    use flying_bird_sim::render::{self, draw_background, draw_warning, draw_world};
+    use flying_bird_sim::theme::{player_skins, Animator, Palette};
+    use flying_bird_sim::{World, HISTORY_LEN};
+
+    use super::*;
+
+    // 1920x1080画成960x540，再小轨迹就看不清了
+    const SCALE: f64 = 0.5;
+    // 抗锯齿在不同的CPU上可能差一点点
+    const TOLERANCE: u8 = 2;
+
+    // 固定的种子，1号鸟让自动驾驶飞，2号鸟一直不按，很快就会撞
+    fn scene(players: usize, steps: usize) -> (World, [(Anim, usize); 2]) {
+        let mut world = World::new(1920., 1080., players, 5);
+        let mut pilot = Autopilot::default();
+        let mut animators: [Animator; 2] = Default::default();
+        let mut poses = Default::default();
+        for _ in 0..steps {
+            world.birds[0].flying = pilot.decide(&world, 0);
+            world.step();
+            poses = render::poses(&world, &mut animators);
+        }
+        (world, poses)
+    }
+
+    fn draw(
+        world: &World,
+        poses: &[(Anim, usize); 2],
+        skin: &'static Skin,
+        high_contrast: bool,
+    ) -> Pixmap {
+        let skins = player_skins(skin);
+        let sprites = skins.map(bird_sprite);
+        let palette = Palette::get(high_contrast);
+        let (w, h) = ((world.w * SCALE) as u32, (world.h * SCALE) as u32);
+        let mut pixmap = Pixmap::new(w, h).unwrap();
+        let mut renderer = SkiaRenderer::new(&mut pixmap, &sprites, SCALE);
+        draw_background(&mut renderer, world, palette);
+        draw_world(
+            &mut renderer,
+            world,
+            poses,
+            skins.map(|skin| skin.tint),
+            palette,
+            HISTORY_LEN,
+        );
+        draw_warning(&mut renderer, world, palette);
+        pixmap
+    }
+
+    // 画法改了是故意的话，用UPDATE_GOLDEN=1 cargo test -p flying-bird-gif重新生成
+    fn check(name: &str, pixmap: &Pixmap) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(format!("{name}.png"));
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            pixmap.save_png(&path).unwrap();
+            return;
+        }
+        let golden = Pixmap::load_png(&path).unwrap_or_else(|e| {
+            panic!(
+                "can't read {}: {e}, run with UPDATE_GOLDEN=1",
+                path.display()
+            )
+        });
+        let same = golden.width() == pixmap.width()
+            && golden.height() == pixmap.height()
+            && golden
+                .data()
+                .iter()
+                .zip(pixmap.data())
+                .all(|(a, b)| a.abs_diff(*b) <= TOLERANCE);
+        if !same {
+            let actual = env::temp_dir().join(format!("flying-bird-{name}.png"));
+            pixmap.save_png(&actual).unwrap();
+            panic!(
+                "{name} doesn't match {}, got {}",
+                path.display(),
+                actual.display()
+            );
+        }
+    }
+
+    #[test]
+    fn single_bird() {
+        let (world, poses) = scene(1, 400);
+        assert!(world.birds[0].alive);
+        check("single", &draw(&world, &poses, Skin::by_id("robin"), false));
+    }
+
+    #[test]
+    fn two_players_one_crashed() {
+        // 2号鸟刚撞在地上，还在画面里
+        let (world, poses) = scene(2, 140);
+        assert!(world.birds[0].alive && !world.birds[1].alive);
+        check(
+            "two_players",
+            &draw(&world, &poses, Skin::by_id("classic"), true),
+        );
+    }
+}
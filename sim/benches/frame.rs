@@ -0,0 +1,81 @@
+// 每一帧模拟部分的开销，和以前的做法比一比，Yew重新渲染和画图不在里面
+// cargo bench -p flying-bird-sim
+// 以前每帧先把整个世界复制一份再算，轨迹是Vec，每次插到最前面
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::time::Instant;
+
+use flying_bird_sim::replay::Recorder;
+use flying_bird_sim::{World, HISTORY_LEN};
+
+const FRAMES: u32 = 20_000;
+// 跑几遍取最快的一遍，少受别的程序干扰
+const ROUNDS: usize = 5;
+// 和网页里一样，录最近3秒
+const CLIP_FRAMES: usize = 375;
+
+// 飞满一条轨迹，撞了也不停
+fn flying_world() -> World {
+    let mut world = World::new(1920., 1080., 1, 42);
+    world.invincible = true;
+    for frame in 0..HISTORY_LEN as u32 {
+        fly(&mut world, frame);
+    }
+    world
+}
+
+// 按一会儿松一会儿
+fn fly(world: &mut World, frame: u32) {
+    world.birds[0].flying = frame % 40 < 12;
+    world.step();
+}
+
+fn bench(name: &str, mut run: impl FnMut(u32)) {
+    let best = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for frame in 0..FRAMES {
+                run(frame);
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap();
+    println!(
+        "{name:<28}{:>8.0} ns/frame",
+        best.as_nanos() as f64 / FRAMES as f64
+    );
+}
+
+fn main() {
+    let mut world = flying_world();
+    bench("step in place", |frame| fly(&mut world, frame));
+
+    let mut world = flying_world();
+    bench("clone, step, replace", |frame| {
+        let mut next = world.clone();
+        fly(&mut next, frame);
+        world = next;
+    });
+
+    let mut world = flying_world();
+    let mut recorder = Recorder::new(CLIP_FRAMES);
+    bench("step and record", |frame| {
+        fly(&mut world, frame);
+        recorder.record(&world);
+    });
+
+    let mut trail: Vec<(f64, f64)> = vec![(0., 0.); HISTORY_LEN];
+    bench("trail: Vec insert(0)", |frame| {
+        trail.insert(0, (frame as f64, 0.));
+        trail.truncate(HISTORY_LEN);
+        black_box(&trail);
+    });
+
+    let mut trail: VecDeque<(f64, f64)> = vec![(0., 0.); HISTORY_LEN].into();
+    bench("trail: ring buffer", |frame| {
+        trail.pop_back();
+        trail.push_front((frame as f64, 0.));
+        black_box(&trail);
+    });
+}
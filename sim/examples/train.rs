@@ -0,0 +1,86 @@
+// 用交叉熵方法训练一个线性策略：观测加权求和大于0就按
+// cargo run --release -p flying-bird-sim --example train
+use flying_bird_sim::env::{run_batch, EnvConfig};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const ITERATIONS: usize = 40;
+const POPULATION: usize = 64;
+const ELITES: usize = 8;
+// 每个候选策略飞几局取平均
+const EPISODES: usize = 4;
+const INIT_STD: f64 = 1.;
+// 防止方差缩得太快
+const EXTRA_STD: f64 = 0.05;
+
+fn normal(rng: &mut StdRng) -> f64 {
+    let (u1, u2): (f64, f64) = (1. - rng.gen::<f64>(), rng.gen());
+    (-2. * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+// 最后一个权重是偏置
+fn act(weights: &[f64], obs: &[f64]) -> bool {
+    let (bias, weights) = weights.split_last().unwrap();
+    weights.iter().zip(obs).map(|(w, x)| w * x).sum::<f64>() + bias > 0.
+}
+
+fn main() {
+    let config = EnvConfig {
+        max_steps: 5000,
+        ..Default::default()
+    };
+    let dim = config.observation.len() + 1;
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut mean = vec![0.; dim];
+    let mut std = vec![INIT_STD; dim];
+
+    for iteration in 0..ITERATIONS {
+        let candidates: Vec<Vec<f64>> = (0..POPULATION)
+            .map(|_| {
+                mean.iter()
+                    .zip(&std)
+                    .map(|(m, s)| m + s * normal(&mut rng))
+                    .collect()
+            })
+            .collect();
+        // 每一轮换一批赛道，免得只记住了某几个
+        let base: u64 = rng.gen();
+        let seeds: Vec<u64> = (0..POPULATION * EPISODES)
+            .map(|i| base.wrapping_add((i % EPISODES) as u64))
+            .collect();
+        let episodes = run_batch(&config, &seeds, |i, obs| {
+            act(&candidates[i / EPISODES], obs)
+        });
+
+        let mut ranked: Vec<(f64, usize)> = episodes
+            .chunks(EPISODES)
+            .map(|eps| eps.iter().map(|e| e.reward).sum::<f64>() / EPISODES as f64)
+            .zip(0..)
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let elites: Vec<&Vec<f64>> = ranked[..ELITES]
+            .iter()
+            .map(|&(_, i)| &candidates[i])
+            .collect();
+        for d in 0..dim {
+            mean[d] = elites.iter().map(|e| e[d]).sum::<f64>() / ELITES as f64;
+            let var = elites.iter().map(|e| (e[d] - mean[d]).powi(2)).sum::<f64>() / ELITES as f64;
+            std[d] = var.sqrt() + EXTRA_STD;
+        }
+
+        let best = &episodes[ranked[0].1 * EPISODES..][..EPISODES];
+        println!(
+            "iteration {iteration:>2}: best reward {:>7.2}, mean reward {:>7.2}, best scores {:?}",
+            ranked[0].0,
+            ranked.iter().map(|r| r.0).sum::<f64>() / POPULATION as f64,
+            best.iter().map(|e| e.score).collect::<Vec<_>>()
+        );
+    }
+
+    // 在没见过的赛道上试一试
+    let seeds: Vec<u64> = (1_000_000..1_000_100).collect();
+    let episodes = run_batch(&config, &seeds, |_, obs| act(&mean, obs));
+    let average = episodes.iter().map(|e| e.score).sum::<u32>() as f64 / seeds.len() as f64;
+    println!("weights {mean:.3?}");
+    println!("average score on {} new seeds: {average:.1}", seeds.len());
+}
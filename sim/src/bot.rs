@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::f64::consts::TAU;
+
+use crate::{Bird, World, OB_WIDTH};
+
+// 每隔这么多步重新决定一次按还是不按
+const SEGMENT: usize = 8;
+// 往前看多少段，大约1.3秒
+const HORIZON: usize = 20;
+// 每次规划最多试飞多少段，防止卡住一帧
+const BUDGET: u32 = 3000;
+// 位置和角度差不多的状态只试一次
+const POS_CELL: f64 = 6.;
+const ANGLE_CELL: f64 = 0.05;
+const MAX_CLIMB: f64 = 1.1;
+const MAX_DIVE: f64 = 1.5;
+const SAFE_ANGLE: f64 = 0.6;
+const SAFE_MARGIN: f64 = 0.2;
+// 按现在的方向估计一会儿之后的高度
+const LOOKAHEAD: f64 = 100.;
+
+// 按照真实的飞行模型往前试飞，找一串不会撞的按/松
+#[derive(Default)]
+pub struct Autopilot {
+    // 每段是否按着
+    plan: Vec<bool>,
+    // 当前这段已经走了几步
+    steps: usize,
+    // 计划的轨迹，世界坐标
+    path: Vec<(f64, f64)>,
+}
+
+// 换算到(-π, π]
+fn normalized_angle(bird: &Bird) -> f64 {
+    (bird.angle + TAU / 2.).rem_euclid(TAU) - TAU / 2.
+}
+
+struct Search<'a> {
+    world: &'a World,
+    // 上次的计划，优先沿用，免得来回抖
+    hint: &'a [bool],
+    visited: HashSet<(usize, i64, i64)>,
+    budget: u32,
+    plan: Vec<bool>,
+    best: Vec<bool>,
+}
+
+impl Search<'_> {
+    // 飞一段，撞了返回None
+    fn fly_segment(&self, bird: &Bird, flying: bool) -> Option<Bird> {
+        let mut bird = bird.probe();
+        bird.flying = flying;
+        bird.climb_rate = 1.0;
+        for _ in 0..SEGMENT {
+            if self.world.collision(&bird).is_some() {
+                return None;
+            }
+            bird.fly(self.world.h);
+        }
+        Some(bird)
+    }
+
+    fn is_safe(&self, bird: &Bird) -> bool {
+        let y = self.world.bird_y(bird) / self.world.h;
+        normalized_angle(bird).abs() < SAFE_ANGLE && (SAFE_MARGIN..1. - SAFE_MARGIN).contains(&y)
+    }
+
+    // 先试往下一个缺口中间飞的那个
+    fn prefer(&self, bird: &Bird, depth: usize) -> bool {
+        let target = self
+            .world
+            .obstacles
+            .iter()
+            .find(|ob| ob.x + OB_WIDTH > bird.x)
+            .map(|ob| (ob.y1 + ob.y2) / 2.)
+            .unwrap_or(self.world.h / 2.);
+        let ahead = self.world.bird_y(bird) + LOOKAHEAD * bird.angle.sin();
+        if (ahead - target).abs() < POS_CELL {
+            return self.hint.get(depth).copied().unwrap_or(false);
+        }
+        ahead > target
+    }
+
+    fn dfs(&mut self, bird: &Bird) -> bool {
+        let depth = self.plan.len();
+        if depth > self.best.len() {
+            self.best = self.plan.clone();
+        }
+        // 再往后还没生成障碍物，看不到了，停在一个随时能调整的状态才算安全
+        let known = self.world.obstacles.last().map_or(0., |ob| ob.x + OB_WIDTH);
+        if depth == HORIZON || bird.x > known {
+            return self.is_safe(bird);
+        }
+
+        let preferred = self.prefer(bird, depth);
+        for flying in [preferred, !preferred] {
+            if self.budget == 0 {
+                return false;
+            }
+            self.budget -= 1;
+            // 太陡了会失速往回飞，救不回来
+            let Some(next) = self
+                .fly_segment(bird, flying)
+                .filter(|b| (-MAX_CLIMB..MAX_DIVE).contains(&normalized_angle(b)))
+            else {
+                continue;
+            };
+            let key = (
+                depth,
+                (next.pos / POS_CELL).round() as i64,
+                (next.angle.rem_euclid(TAU) / ANGLE_CELL).round() as i64,
+            );
+            if !self.visited.insert(key) {
+                continue;
+            }
+            self.plan.push(flying);
+            if self.dfs(&next) {
+                return true;
+            }
+            self.plan.pop();
+        }
+        false
+    }
+}
+
+impl Autopilot {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn path(&self) -> &[(f64, f64)] {
+        &self.path
+    }
+
+    fn replan(&mut self, world: &World, bird: &Bird) {
+        // 刚走完一段，旧计划往前挪一段当作提示
+        let hint: Vec<bool> = self.plan.iter().skip(1).copied().collect();
+        let mut search = Search {
+            world,
+            hint: &hint,
+            visited: HashSet::new(),
+            budget: BUDGET,
+            plan: vec![],
+            best: vec![],
+        };
+        // 怎么都要撞就选撑得最久的
+        self.plan = if search.dfs(bird) {
+            search.plan
+        } else {
+            search.best
+        };
+
+        self.path.clear();
+        let mut probe = bird.probe();
+        probe.climb_rate = 1.0;
+        for flying in self.plan.iter() {
+            probe.flying = *flying;
+            for _ in 0..SEGMENT {
+                probe.fly(world.h);
+                self.path.push((probe.x, world.bird_y(&probe)));
+            }
+        }
+    }
+
+    // 每一步调用，返回这一步要不要按
+    pub fn decide(&mut self, world: &World, bird: usize) -> bool {
+        if self.steps == 0 || self.steps == SEGMENT {
+            self.steps = 0;
+            self.replan(world, &world.birds[bird]);
+        }
+        // 走过的轨迹不用再画
+        if !self.path.is_empty() {
+            self.path.remove(0);
+        }
+        self.steps += 1;
+        self.plan.first().copied().unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    // 连续一段种子，每个飞到MIN_SCORE或者撞了为止
+    const SEEDS: std::ops::Range<u64> = 0..20;
+    const MIN_SCORE: u32 = 50;
+    // 现在20个里有14个能飞到50，平均41，不能比这个差
+    const MIN_PASSES: usize = 14;
+    const MIN_MEAN: f64 = 40.;
+
+    fn fly(seed: u64) -> u32 {
+        let mut world = World::new(1920., 1080., 1, seed);
+        let mut pilot = Autopilot::default();
+        while world.birds[0].alive && world.birds[0].score < MIN_SCORE {
+            world.birds[0].flying = pilot.decide(&world, 0);
+            world.step();
+        }
+        world.birds[0].score
+    }
+
+    #[test]
+    fn clears_most_courses() {
+        let scores: Vec<u32> = SEEDS.map(fly).collect();
+        let passes = scores.iter().filter(|s| **s >= MIN_SCORE).count();
+        let mean = scores.iter().sum::<u32>() as f64 / scores.len() as f64;
+        assert!(
+            passes >= MIN_PASSES && mean >= MIN_MEAN,
+            "{passes} of {} courses cleared, mean score {mean}: {scores:?}",
+            scores.len()
+        );
+    }
+
+    // 同样每SEGMENT步决定一次，不限看多远，把所有走法都试一遍，能不能飞过前n个障碍物
+    fn passable(seed: u64, n: usize) -> bool {
+        let mut world = World::new(1920., 1080., 1, seed);
+        while world.obstacles.len() <= n {
+            world.generate();
+        }
+        let goal = world.obstacles[n - 1].x + OB_WIDTH;
+        let mut layer = vec![world.birds[0].probe()];
+        while !layer.is_empty() {
+            let mut next = HashMap::new();
+            for bird in layer.iter() {
+                for flying in [false, true] {
+                    let mut bird = bird.probe();
+                    bird.flying = flying;
+                    for _ in 0..SEGMENT {
+                        if world.collision(&bird).is_some() {
+                            break;
+                        }
+                        bird.fly(world.h);
+                    }
+                    if world.collision(&bird).is_some() {
+                        continue;
+                    }
+                    if bird.x > goal {
+                        return true;
+                    }
+                    let key = (
+                        (bird.pos / POS_CELL).round() as i64,
+                        (bird.angle.rem_euclid(TAU) / ANGLE_CELL).round() as i64,
+                    );
+                    next.entry(key).or_insert(bird);
+                }
+            }
+            layer = next.into_values().collect();
+        }
+        false
+    }
+
+    // 种子7上第3个障碍物就撞了，但赛道本身飞得过去，是自动驾驶看得不够远
+    #[test]
+    fn seed_7_is_a_planner_failure() {
+        assert!(fly(7) < 3);
+        assert!(passable(7, 4));
+    }
+}
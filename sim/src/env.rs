@@ -0,0 +1,416 @@
+// 给强化学习用的无头环境，一步就是网页里的一帧
+use std::thread;
+
+use crate::{Bird, SimEvent, World, OB_WIDTH, V_MAX_2};
+
+// 还没生成的障碍物当成很远处一个全开的缺口
+const FAR_DISTANCE: f64 = 2.;
+
+// 观测里放哪些量，都除过世界的高度或者最大速度，大致在[-1, 1]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObservationConfig {
+    // 鸟的高度，0是顶，1是底
+    pub bird_y: bool,
+    // 朝向的sin和cos，占两个位置
+    pub angle: bool,
+    pub speed: bool,
+    // 往前看几个障碍物，每个给出(到鸟的水平距离, 缺口上沿, 缺口下沿)
+    pub obstacles: usize,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        Self {
+            bird_y: true,
+            angle: true,
+            speed: true,
+            obstacles: 2,
+        }
+    }
+}
+
+impl ObservationConfig {
+    pub fn len(&self) -> usize {
+        self.bird_y as usize + 2 * self.angle as usize + self.speed as usize + 3 * self.obstacles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn observe(&self, world: &World, bird: &Bird) -> Vec<f64> {
+        let mut obs = Vec::with_capacity(self.len());
+        if self.bird_y {
+            obs.push(world.bird_y(bird) / world.h);
+        }
+        if self.angle {
+            let (sin, cos) = bird.angle.sin_cos();
+            obs.extend([sin, cos]);
+        }
+        if self.speed {
+            obs.push(bird.speed(world.h) / V_MAX_2.sqrt());
+        }
+        let mut ahead = world
+            .obstacles
+            .iter()
+            .filter(|ob| ob.x + OB_WIDTH > bird.x)
+            .map(|ob| [(ob.x - bird.x) / world.h, ob.y1 / world.h, ob.y2 / world.h]);
+        for _ in 0..self.obstacles {
+            obs.extend(ahead.next().unwrap_or([FAR_DISTANCE, 0., 1.]));
+        }
+        obs
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvConfig {
+    // 世界的大小，和联机模式一样高
+    pub w: f64,
+    pub h: f64,
+    pub observation: ObservationConfig,
+    // 每穿过一个障碍物
+    pub reward_pass: f64,
+    // 每活过一步，不给的话一开始怎么飞都是0分，学不动
+    pub reward_step: f64,
+    pub reward_crash: f64,
+    // 飞了这么多步还没撞也算结束
+    pub max_steps: u32,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            w: 1820.,
+            h: 1024.,
+            observation: ObservationConfig::default(),
+            reward_pass: 1.,
+            reward_step: 0.01,
+            reward_crash: -1.,
+            max_steps: 20_000,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Env {
+    config: EnvConfig,
+    world: World,
+    steps: u32,
+}
+
+impl Env {
+    // 用之前先reset
+    pub fn new(config: EnvConfig) -> Self {
+        let world = World::new(config.w, config.h, 1, 0);
+        Self {
+            config,
+            world,
+            steps: 0,
+        }
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    pub fn score(&self) -> u32 {
+        self.world.birds[0].score
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.world.is_over() || self.steps >= self.config.max_steps
+    }
+
+    pub fn observe(&self) -> Vec<f64> {
+        self.config
+            .observation
+            .observe(&self.world, &self.world.birds[0])
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Vec<f64> {
+        self.world = World::new(self.config.w, self.config.h, 1, seed);
+        self.steps = 0;
+        self.observe()
+    }
+
+    // action是这一步按没按着
+    pub fn step(&mut self, action: bool) -> (Vec<f64>, f64, bool) {
+        if self.is_done() {
+            return (self.observe(), 0., true);
+        }
+        self.world.birds[0].flying = action;
+        let mut reward = 0.;
+        for event in self.world.step() {
+            match event {
+                SimEvent::Passed { .. } => reward += self.config.reward_pass,
+                SimEvent::Crashed { .. } => reward += self.config.reward_crash,
+                SimEvent::Hit { .. } | SimEvent::Looped { .. } => {}
+            }
+        }
+        self.steps += 1;
+        if self.world.birds[0].alive {
+            reward += self.config.reward_step;
+        }
+        (self.observe(), reward, self.is_done())
+    }
+}
+
+// 一组环境一起走，结束了的自动换下一个种子重开
+pub struct VecEnv {
+    envs: Vec<Env>,
+    next_seed: u64,
+}
+
+impl VecEnv {
+    pub fn new(config: EnvConfig, n: usize) -> Self {
+        Self {
+            envs: vec![Env::new(config); n],
+            next_seed: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[Env] {
+        &self.envs
+    }
+
+    // 第i个环境的种子是seed + i
+    pub fn reset(&mut self, seed: u64) -> Vec<Vec<f64>> {
+        self.next_seed = seed;
+        self.envs
+            .iter_mut()
+            .map(|env| {
+                let obs = env.reset(self.next_seed);
+                self.next_seed += 1;
+                obs
+            })
+            .collect()
+    }
+
+    // done为true时返回的观测已经是新一局的了
+    pub fn step(&mut self, actions: &[bool]) -> Vec<(Vec<f64>, f64, bool)> {
+        assert_eq!(actions.len(), self.envs.len());
+        self.envs
+            .iter_mut()
+            .zip(actions)
+            .map(|(env, action)| {
+                let (obs, reward, done) = env.step(*action);
+                if !done {
+                    return (obs, reward, done);
+                }
+                let obs = env.reset(self.next_seed);
+                self.next_seed += 1;
+                (obs, reward, done)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Episode {
+    pub seed: u64,
+    pub reward: f64,
+    pub score: u32,
+    pub steps: u32,
+}
+
+// 每个种子完整飞一局，分到各个CPU核上跑
+// policy的第一个参数是第几局，方便一批里试不同的策略
+pub fn run_batch<F>(config: &EnvConfig, seeds: &[u64], policy: F) -> Vec<Episode>
+where
+    F: Fn(usize, &[f64]) -> bool + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = seeds.len().div_ceil(threads).max(1);
+    let policy = &policy;
+    thread::scope(|scope| {
+        let workers: Vec<_> = seeds
+            .chunks(chunk)
+            .enumerate()
+            .map(|(c, seeds)| {
+                scope.spawn(move || {
+                    let mut env = Env::new(config.clone());
+                    seeds
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &seed)| {
+                            let mut obs = env.reset(seed);
+                            let mut total = 0.;
+                            loop {
+                                let (next, reward, done) = env.step(policy(c * chunk + i, &obs));
+                                total += reward;
+                                if done {
+                                    break;
+                                }
+                                obs = next;
+                            }
+                            Episode {
+                                seed,
+                                reward: total,
+                                score: env.score(),
+                                steps: env.steps(),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Autopilot;
+
+    // 只看高度：低于中线就往上飞
+    fn hold_middle(obs: &[f64]) -> bool {
+        obs[0] > 0.5
+    }
+
+    #[test]
+    fn same_seed_same_episode() {
+        let mut a = Env::new(EnvConfig::default());
+        let mut b = Env::new(EnvConfig::default());
+        let mut c = Env::new(EnvConfig::default());
+        let mut obs = a.reset(42);
+        assert_eq!(obs, b.reset(42));
+        c.reset(43);
+        for _ in 0..200 {
+            let action = hold_middle(&obs);
+            let (next, reward, _) = a.step(action);
+            assert_eq!(b.step(action), (next.clone(), reward, false));
+            c.step(action);
+            obs = next;
+        }
+        assert_ne!(a.observe(), c.observe());
+    }
+
+    #[test]
+    fn rewards_passing_and_crashing() {
+        let config = EnvConfig::default();
+        let mut env = Env::new(config.clone());
+        env.reset(0);
+        let mut pilot = Autopilot::default();
+        loop {
+            let action = pilot.decide(env.world(), 0);
+            let (_, reward, done) = env.step(action);
+            assert!(!done);
+            if env.score() == 1 {
+                assert_eq!(reward, config.reward_pass + config.reward_step);
+                break;
+            }
+            assert_eq!(reward, config.reward_step);
+        }
+
+        // 不按就掉到地上
+        let reward = loop {
+            let (_, reward, done) = env.step(false);
+            if done {
+                break reward;
+            }
+        };
+        assert_eq!(reward, config.reward_crash);
+        assert!(!env.world().birds[0].alive);
+        assert_eq!(env.step(false).1, 0.);
+    }
+
+    #[test]
+    fn observation_has_the_configured_length() {
+        for observation in [
+            ObservationConfig::default(),
+            ObservationConfig {
+                bird_y: false,
+                angle: true,
+                speed: false,
+                obstacles: 0,
+            },
+            ObservationConfig {
+                obstacles: 5,
+                ..Default::default()
+            },
+        ] {
+            let len = observation.len();
+            let mut env = Env::new(EnvConfig {
+                observation,
+                ..Default::default()
+            });
+            assert_eq!(env.reset(1).len(), len);
+            assert_eq!(env.step(true).0.len(), len);
+        }
+    }
+
+    #[test]
+    fn vec_env_resets_finished_envs() {
+        let mut envs = VecEnv::new(EnvConfig::default(), 2);
+        envs.reset(10);
+        // 第一个一直不按很快撞，第二个一直飞
+        let mut first_done = None;
+        for step in 0..1000 {
+            let results = envs.step(&[false, hold_middle(&envs.envs()[1].observe())]);
+            if results[0].2 {
+                first_done = Some(step);
+                break;
+            }
+        }
+        assert!(first_done.is_some());
+        let (first, second) = (&envs.envs()[0], &envs.envs()[1]);
+        assert_eq!(first.steps(), 0);
+        assert!(first.world().birds[0].alive);
+        assert_eq!(second.steps(), first_done.unwrap() + 1);
+    }
+
+    #[test]
+    fn run_batch_matches_running_one_by_one() {
+        let config = EnvConfig {
+            max_steps: 3000,
+            ..Default::default()
+        };
+        let seeds: Vec<u64> = (0..6).collect();
+        // 单数局换个飞法，看第几局有没有传对
+        let policy = |i: usize, obs: &[f64]| hold_middle(obs) || (i % 2 == 1 && obs[1] > 0.9);
+        let batch = run_batch(&config, &seeds, policy);
+
+        let mut env = Env::new(config);
+        for (i, (&seed, episode)) in seeds.iter().zip(&batch).enumerate() {
+            let mut obs = env.reset(seed);
+            let mut reward = 0.;
+            loop {
+                let (next, r, done) = env.step(policy(i, &obs));
+                reward += r;
+                if done {
+                    break;
+                }
+                obs = next;
+            }
+            assert_eq!(
+                *episode,
+                Episode {
+                    seed,
+                    reward,
+                    score: env.score(),
+                    steps: env.steps(),
+                }
+            );
+        }
+    }
+}
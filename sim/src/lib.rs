@@ -0,0 +1,652 @@
+// 游戏的物理和规则，不依赖浏览器，网页和本地的强化学习环境用的是同一份
+pub mod bot;
+pub mod env;
+pub mod render;
+pub mod replay;
+pub mod rewind;
+pub mod theme;
+
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+pub const BIRD_SIZE: f64 = 128.;
+pub const CHECK_SIZE: f64 = BIRD_SIZE / 2.0 + 5.0;
+pub const OB_WIDTH: f64 = 100.;
+pub const HISTORY_LEN: usize = 250;
+// 练习最多从第几个障碍物开始，再多生成起来太慢
+pub const MAX_SKIP: u32 = 10000;
+
+const MIN_SPACE: f64 = 3. * BIRD_SIZE;
+// 第一个障碍物从这里往后排
+const FIRST_OB_X: f64 = 8. * BIRD_SIZE;
+const V_MIN_2: f64 = 9.0;
+const V_MAX_2: f64 = 81.0;
+const ROTATE_UP: f64 = -0.022;
+const ROTATE_DOWN_D: f64 = 0.12;
+
+// bird.webp的外轮廓(深色描边)，以图片中心为原点，每隔6px取一个点
+// 以前是读canvas像素看描边有没有碰到障碍物，现在直接算这些点
+pub const HITBOX: [(f64, f64); 65] = [
+    (-56.5, -1.5),
+    (-57.5, -7.5),
+    (-56.5, -13.5),
+    (-56.5, -19.5),
+    (-55.5, -25.5),
+    (-49.5, -25.5),
+    (-43.5, -22.5),
+    (-29.5, -15.5),
+    (-37.5, -20.5),
+    (-29.5, -21.5),
+    (-32.5, -27.5),
+    (-35.5, -33.5),
+    (-37.5, -39.5),
+    (-38.5, -45.5),
+    (-39.5, -51.5),
+    (-37.5, -57.5),
+    (-32.5, -61.5),
+    (-26.5, -60.5),
+    (-20.5, -59.5),
+    (-13.5, -60.5),
+    (-7.5, -59.5),
+    (-1.5, -57.5),
+    (4.5, -53.5),
+    (10.5, -49.5),
+    (14.5, -43.5),
+    (17.5, -37.5),
+    (19.5, -31.5),
+    (21.5, -25.5),
+    (27.5, -24.5),
+    (33.5, -24.5),
+    (39.5, -24.5),
+    (45.5, -23.5),
+    (50.5, -18.5),
+    (53.5, -12.5),
+    (56.5, -6.5),
+    (61.5, -1.5),
+    (54.5, 4.5),
+    (47.5, 5.5),
+    (40.5, 7.5),
+    (33.5, 10.5),
+    (28.5, 14.5),
+    (27.5, 20.5),
+    (25.5, 26.5),
+    (24.5, 32.5),
+    (21.5, 38.5),
+    (17.5, 44.5),
+    (12.5, 49.5),
+    (7.5, 54.5),
+    (1.5, 58.5),
+    (-8.5, 60.5),
+    (-14.5, 60.5),
+    (-23.5, 61.5),
+    (-27.5, 56.5),
+    (-28.5, 50.5),
+    (-29.5, 44.5),
+    (-30.5, 38.5),
+    (-30.5, 32.5),
+    (-29.5, 26.5),
+    (-28.5, 20.5),
+    (-27.5, 14.5),
+    (-34.5, 17.5),
+    (-42.5, 20.5),
+    (-51.5, 16.5),
+    (-53.5, 10.5),
+    (-55.5, 4.5),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashCause {
+    Ceiling,
+    Floor,
+    TopPipe,
+    BottomPipe,
+    // 多人时落后太多，出了屏幕左边
+    LeftBehind,
+}
+
+impl CrashCause {
+    // 单人模式只会有这几种
+    pub const ALL: [CrashCause; 4] = [
+        CrashCause::Ceiling,
+        CrashCause::Floor,
+        CrashCause::TopPipe,
+        CrashCause::BottomPipe,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            CrashCause::Ceiling => "ceiling",
+            CrashCause::Floor => "floor",
+            CrashCause::TopPipe => "top_pipe",
+            CrashCause::BottomPipe => "bottom_pipe",
+            CrashCause::LeftBehind => "left_behind",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CrashCause::Ceiling => "ceiling",
+            CrashCause::Floor => "floor",
+            CrashCause::TopPipe => "top pipe",
+            CrashCause::BottomPipe => "bottom pipe",
+            CrashCause::LeftBehind => "left behind",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub x: f64,
+    pub y1: f64,
+    pub y2: f64,
+}
+
+impl Obstacle {
+    // 位置只和上一个障碍物、第几个有关，和屏幕大小、帧率无关
+    // 这样同一个种子在哪台设备上都是同样的赛道
+    fn random_gen(rng: &mut StdRng, last: Option<&Obstacle>, index: u32, h: f64) -> Self {
+        // 以前用当前得分调难度，生成时鸟一般还差一个障碍物没过
+        let level = index.saturating_sub(1) as f64;
+        let dis = rng.gen_range(0.0..(6.0 - level).max(4.0) * OB_WIDTH)
+            + (3.0 - level).max(0.0) * OB_WIDTH;
+        let last_x = last.map(|ob| ob.x).unwrap_or(FIRST_OB_X);
+        let last_y1 = last.map(|ob| ob.y1).unwrap_or(h / 3.0);
+
+        let space = rng.gen_range(MIN_SPACE..1.5 * MIN_SPACE);
+        let max_dy = (dis / OB_WIDTH).powi(2) * BIRD_SIZE;
+        let y1 = rng.gen_range(
+            (last_y1 - max_dy).max(0.0).min(h - space - 1.0)..(last_y1 + max_dy).min(h - space),
+        );
+
+        Self {
+            x: last_x + dis + 2.0 * OB_WIDTH,
+            y1,
+            y2: y1 + space,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Bird {
+    // 世界坐标
+    pub x: f64,
+    // 相对屏幕中线
+    pub pos: f64,
+    pub angle: f64,
+    pub flying: bool,
+    // 按住时转得多快，手柄扳机用
+    pub climb_rate: f64,
+    pub alive: bool,
+    pub score: u32,
+    // 分数算到哪里了，倒带以后再飞过同一个障碍物不重复算
+    pub scored_x: f64,
+    // 轨迹，世界坐标，最新的在前面，满了就把最老的挤掉
+    pub history: VecDeque<(f64, f64)>,
+}
+
+impl Bird {
+    fn new() -> Self {
+        Self {
+            x: 0.,
+            pos: 0.,
+            angle: 0.,
+            flying: false,
+            climb_rate: 1.0,
+            alive: true,
+            score: 0,
+            scored_x: 0.,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    // 不带轨迹的副本，往前试飞用
+    pub fn probe(&self) -> Self {
+        Self {
+            history: VecDeque::new(),
+            ..*self
+        }
+    }
+
+    pub fn speed(&self, h: f64) -> f64 {
+        (V_MIN_2 + ((h / 2. + self.pos) / h) * (V_MAX_2 - V_MIN_2)).sqrt()
+    }
+
+    // 飞一步，只算运动不管碰撞，返回是否转过了一整圈
+    pub fn fly(&mut self, h: f64) -> bool {
+        let v = self.speed(h);
+        let (sin, cos) = self.angle.sin_cos();
+        let (xl, yl) = (v * cos, v * sin);
+        self.pos += yl;
+        let new_angle = if self.flying {
+            self.angle + ROTATE_UP * self.climb_rate
+        } else {
+            (yl + ROTATE_DOWN_D).atan2(xl)
+        };
+        // 一直按住会转圈，每多转过一整圈算一次
+        let looped = (-new_angle / TAU).floor() > (-self.angle / TAU).floor();
+        self.angle = new_angle;
+        self.x += xl;
+        looped
+    }
+
+    pub fn hitbox(&self, y: f64) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let (sin, cos) = self.angle.sin_cos();
+        HITBOX
+            .iter()
+            .map(move |(px, py)| (self.x + px * cos - py * sin, y + px * sin + py * cos))
+    }
+}
+
+pub enum SimEvent {
+    Passed { bird: usize, obstacle: Obstacle },
+    Crashed { bird: usize, cause: CrashCause },
+    // 不会死的时候撞上了，每一帧都会有
+    Hit { bird: usize, cause: CrashCause },
+    Looped { bird: usize },
+}
+
+#[derive(Clone)]
+pub struct World {
+    pub w: f64,
+    pub h: f64,
+    pub birds: Vec<Bird>,
+    pub obstacles: Vec<Obstacle>,
+    // 练习时撞了也不死，直接穿过去
+    pub invincible: bool,
+    // 一共生成过几个障碍物
+    generated: u32,
+    rng: StdRng,
+}
+
+impl World {
+    pub fn new(w: f64, h: f64, n_birds: usize, seed: u64) -> Self {
+        Self {
+            w,
+            h,
+            birds: (0..n_birds).map(|_| Bird::new()).collect(),
+            obstacles: vec![],
+            invincible: false,
+            generated: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // 练习时从第start个障碍物前面开始，赛道和从头飞的一样，只能在刚建好的世界上用
+    pub fn skip_to(&mut self, start: u32) {
+        let start = start.min(MAX_SKIP);
+        if self.obstacles.is_empty() {
+            self.generate();
+        }
+        let first_x = self.obstacles[0].x;
+        // 边生成边扔掉肯定出不了屏幕的，不然障碍物越攒越多
+        let keep = self.w / 3. + self.w;
+        while self.generated <= start {
+            self.generate();
+            let last_x = self.obstacles[self.obstacles.len() - 1].x;
+            let behind = self
+                .obstacles
+                .partition_point(|ob| ob.x <= last_x - first_x - keep);
+            self.obstacles.drain(..behind);
+        }
+        // 和从头开始时离第一个障碍物一样远
+        let index = self.obstacles.len() - (self.generated - start) as usize;
+        let x = self.obstacles[index].x - first_x;
+        for bird in self.birds.iter_mut() {
+            bird.x = x;
+            bird.score = start;
+        }
+        let screen_left = self.camera_x() - self.w / 3.;
+        self.obstacles.retain(|ob| ob.x > screen_left - self.w);
+    }
+
+    fn generate(&mut self) {
+        let ob = Obstacle::random_gen(&mut self.rng, self.obstacles.last(), self.generated, self.h);
+        self.generated += 1;
+        self.obstacles.push(ob);
+    }
+
+    pub fn resize(&mut self, w: f64, h: f64) {
+        self.w = w;
+        self.h = h;
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.birds.iter().all(|b| !b.alive)
+    }
+
+    // 镜头跟着飞在最前面的还活着的鸟，它画在屏幕1/3处
+    pub fn camera_x(&self) -> f64 {
+        let leading = |alive_only: bool| {
+            self.birds
+                .iter()
+                .filter(|b| b.alive || !alive_only)
+                .map(|b| b.x)
+                .reduce(f64::max)
+        };
+        leading(true).or_else(|| leading(false)).unwrap_or(0.)
+    }
+
+    pub fn to_screen_x(&self, x: f64) -> f64 {
+        x - self.camera_x() + self.w / 3.
+    }
+
+    pub fn bird_y(&self, bird: &Bird) -> f64 {
+        self.h / 2. + bird.pos
+    }
+
+    pub fn speed(&self, bird: &Bird) -> f64 {
+        bird.speed(self.h)
+    }
+
+    // 鸟正在穿过的障碍物
+    pub fn current_obstacle(&self, bird: &Bird) -> Option<&Obstacle> {
+        self.obstacles
+            .iter()
+            .find(|ob| ob.x - CHECK_SIZE < bird.x && bird.x < ob.x + OB_WIDTH + CHECK_SIZE)
+    }
+
+    // 屏幕右边外面的下一个障碍物
+    pub fn next_obstacle(&self) -> Option<&Obstacle> {
+        let right = self.camera_x() + self.w * 2. / 3.;
+        self.obstacles
+            .iter()
+            .find(|ob| ob.x + OB_WIDTH > right)
+            .filter(|ob| ob.x > right)
+    }
+
+    pub fn collision(&self, bird: &Bird) -> Option<CrashCause> {
+        if bird.x < self.camera_x() - self.w / 3. - BIRD_SIZE / 2. {
+            return Some(CrashCause::LeftBehind);
+        }
+        let y = self.bird_y(bird);
+        // 障碍物按x排好了，附近的是连着的一段
+        let start = self
+            .obstacles
+            .partition_point(|ob| ob.x + OB_WIDTH + CHECK_SIZE <= bird.x);
+        let end = self
+            .obstacles
+            .partition_point(|ob| ob.x - CHECK_SIZE < bird.x);
+        let nearby = &self.obstacles[start..end];
+        for (px, py) in bird.hitbox(y) {
+            for ob in nearby {
+                if ob.x - 1.0 <= px && px <= ob.x + OB_WIDTH + 1.0 {
+                    if py > ob.y2 {
+                        return Some(CrashCause::BottomPipe);
+                    }
+                    if py < ob.y1 {
+                        return Some(CrashCause::TopPipe);
+                    }
+                }
+            }
+            if py > self.h {
+                return Some(CrashCause::Floor);
+            }
+            if py < 0. {
+                return Some(CrashCause::Ceiling);
+            }
+        }
+        None
+    }
+
+    pub fn step(&mut self) -> Vec<SimEvent> {
+        let mut events = vec![];
+
+        // 先检查是否撞击，再计算运动
+        let crashes: Vec<(usize, CrashCause)> = self
+            .birds
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.alive)
+            .filter_map(|(i, b)| self.collision(b).map(|cause| (i, cause)))
+            .collect();
+        for (i, cause) in crashes {
+            if self.invincible {
+                events.push(SimEvent::Hit { bird: i, cause });
+            } else {
+                self.birds[i].alive = false;
+                events.push(SimEvent::Crashed { bird: i, cause });
+            }
+        }
+
+        for i in 0..self.birds.len() {
+            if !self.birds[i].alive {
+                continue;
+            }
+            let h = self.h;
+            let bird = &mut self.birds[i];
+            let old_x = bird.x;
+            if bird.fly(h) {
+                events.push(SimEvent::Looped { bird: i });
+            }
+            // 穿出天花板和地面就拉回来，不然速度算不出来
+            if self.invincible {
+                bird.pos = bird.pos.clamp(-h / 2., h / 2.);
+            }
+            if bird.history.len() == HISTORY_LEN {
+                bird.history.pop_back();
+            }
+            bird.history.push_front((bird.x, h / 2. + bird.pos));
+
+            if let Some(ob) = self
+                .obstacles
+                .iter()
+                .find(|ob| old_x < ob.x + OB_WIDTH && ob.x + OB_WIDTH <= self.birds[i].x)
+                .filter(|ob| ob.x + OB_WIDTH > self.birds[i].scored_x)
+            {
+                self.birds[i].score += 1;
+                self.birds[i].scored_x = ob.x + OB_WIDTH;
+                events.push(SimEvent::Passed {
+                    bird: i,
+                    obstacle: ob.clone(),
+                });
+            }
+        }
+
+        let screen_left = self.camera_x() - self.w / 3.;
+        let screen_right = screen_left + self.w;
+        self.obstacles.retain(|ob| ob.x > screen_left - self.w);
+        while self.obstacles.last().map(|ob| ob.x).unwrap_or(screen_left) < screen_right {
+            self.generate();
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Autopilot;
+
+    // 自动驾驶飞到done为止，返回一路上的事件
+    fn fly_until(world: &mut World, done: impl Fn(&World) -> bool) -> Vec<SimEvent> {
+        let mut pilot = Autopilot::default();
+        let mut events = vec![];
+        for _ in 0..20000 {
+            world.birds[0].flying = pilot.decide(world, 0);
+            events.extend(world.step());
+            if done(world) {
+                return events;
+            }
+        }
+        panic!("never happened");
+    }
+
+    #[test]
+    fn skip_to_gives_the_same_course() {
+        let mut skipped = World::new(1920., 1080., 1, 3);
+        skipped.skip_to(20);
+        skipped.step();
+
+        let mut flown = World::new(1920., 1080., 1, 3);
+        flown.invincible = true;
+        fly_until(&mut flown, |w| w.birds[0].score == 20);
+
+        assert_eq!(skipped.birds[0].score, 20);
+        let x = skipped.obstacles[0].x.max(flown.obstacles[0].x);
+        let ahead = |w: &World| {
+            w.obstacles
+                .iter()
+                .filter(|ob| ob.x >= x)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let (a, b) = (ahead(&skipped), ahead(&flown));
+        let n = a.len().min(b.len());
+        assert!(n > 0);
+        assert!(a[..n] == b[..n]);
+    }
+
+    #[test]
+    fn skip_to_is_bounded() {
+        let mut world = World::new(1920., 1080., 1, 3);
+        world.skip_to(u32::MAX);
+        assert_eq!(world.birds[0].score, MAX_SKIP);
+        assert!(world.obstacles.len() < 20);
+    }
+
+    #[test]
+    fn invincible_birds_only_get_hit() {
+        let mut world = World::new(1920., 1080., 1, 3);
+        world.invincible = true;
+        // 不扇翅膀，一直掉到地上
+        let mut events = vec![];
+        for _ in 0..1000 {
+            events.extend(world.step());
+        }
+        assert!(events.iter().any(|e| matches!(
+            e,
+            SimEvent::Hit {
+                bird: 0,
+                cause: CrashCause::Floor
+            }
+        )));
+        assert!(!events.iter().any(|e| matches!(e, SimEvent::Crashed { .. })));
+        assert!(world.birds[0].alive);
+        assert_eq!(world.birds[0].pos, world.h / 2.);
+    }
+
+    // 鸟在屏幕正中间，前面放一个障碍物
+    fn world_with(n_birds: usize, ob: Obstacle) -> World {
+        let mut world = World::new(1920., 1080., n_birds, 0);
+        world.obstacles = vec![ob];
+        world
+    }
+
+    fn crash(world: &mut World) -> Option<CrashCause> {
+        world.step().into_iter().find_map(|e| match e {
+            SimEvent::Crashed { bird: 0, cause } => Some(cause),
+            _ => None,
+        })
+    }
+
+    fn far_away() -> Obstacle {
+        Obstacle {
+            x: 1000.,
+            y1: 300.,
+            y2: 700.,
+        }
+    }
+
+    #[test]
+    fn crashes_into_ceiling_and_floor() {
+        let mut world = world_with(1, far_away());
+        world.birds[0].pos = -world.h / 2.;
+        assert_eq!(crash(&mut world), Some(CrashCause::Ceiling));
+        assert!(!world.birds[0].alive);
+
+        let mut world = world_with(1, far_away());
+        world.birds[0].pos = world.h / 2.;
+        assert_eq!(crash(&mut world), Some(CrashCause::Floor));
+    }
+
+    #[test]
+    fn crashes_into_pipes() {
+        // 鸟的中心在540
+        let mut world = world_with(
+            1,
+            Obstacle {
+                x: -10.,
+                y1: 600.,
+                y2: 1000.,
+            },
+        );
+        assert_eq!(crash(&mut world), Some(CrashCause::TopPipe));
+
+        let mut world = world_with(
+            1,
+            Obstacle {
+                x: -10.,
+                y1: 100.,
+                y2: 500.,
+            },
+        );
+        assert_eq!(crash(&mut world), Some(CrashCause::BottomPipe));
+
+        // 从空隙里过去没事
+        let mut world = world_with(
+            1,
+            Obstacle {
+                x: -10.,
+                y1: 340.,
+                y2: 740.,
+            },
+        );
+        assert_eq!(crash(&mut world), None);
+        assert!(world.birds[0].alive);
+    }
+
+    #[test]
+    fn left_behind_in_multiplayer() {
+        let mut world = world_with(2, far_away());
+        world.birds[1].x = 2000.;
+        assert_eq!(crash(&mut world), Some(CrashCause::LeftBehind));
+        assert!(world.birds[1].alive);
+    }
+
+    #[test]
+    fn scores_once_per_pipe() {
+        let mut world = world_with(
+            1,
+            Obstacle {
+                x: -OB_WIDTH + 1.,
+                y1: 340.,
+                y2: 740.,
+            },
+        );
+        let passed = |events: Vec<SimEvent>| {
+            events
+                .iter()
+                .filter(|e| matches!(e, SimEvent::Passed { bird: 0, .. }))
+                .count()
+        };
+        assert_eq!(passed(world.step()), 1);
+        assert_eq!(world.birds[0].score, 1);
+        assert_eq!(world.birds[0].scored_x, 1.);
+        assert_eq!(passed(world.step()), 0);
+        assert_eq!(world.birds[0].score, 1);
+    }
+
+    #[test]
+    fn birds_step_independently() {
+        let mut pilot = Autopilot::default();
+        let mut alone = World::new(1920., 1080., 1, 9);
+        let mut pair = World::new(1920., 1080., 2, 9);
+        // 2号一直往下掉，很快撞地
+        for _ in 0..300 {
+            alone.birds[0].flying = pilot.decide(&alone, 0);
+            pair.birds[0].flying = alone.birds[0].flying;
+            alone.step();
+            pair.step();
+            let (a, b) = (&alone.birds[0], &pair.birds[0]);
+            assert_eq!((a.x, a.pos, a.angle), (b.x, b.pos, b.angle));
+        }
+        assert!(pair.birds[0].alive);
+        assert!(!pair.birds[1].alive);
+        assert!(pair.birds[1].x < pair.birds[0].x);
+    }
+}
@@ -0,0 +1,121 @@
+// 一帧怎么画只写一遍：网页上画在canvas上，本地用tiny-skia画，测试拿本地画的和存好的图比
+use crate::theme::{trail_rgb, Anim, Animator, Palette, HISTORY_COLOR_CHANGE, NEXT_OB_WIDTH};
+use crate::{World, OB_WIDTH};
+
+const TRAIL_WIDTH: f64 = 3.;
+// 撞了的鸟和轨迹画成半透明
+const CRASHED_ALPHA: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    // 0..1
+    pub a: f64,
+}
+
+impl Color {
+    pub fn gray(c: u8) -> Self {
+        Self::rgb((c, c, c))
+    }
+
+    pub fn rgb((r, g, b): (u8, u8, u8)) -> Self {
+        Self { r, g, b, a: 1. }
+    }
+
+    pub fn alpha(self, a: f64) -> Self {
+        Self {
+            a: self.a * a,
+            ..self
+        }
+    }
+}
+
+// 坐标都是世界坐标，已经减掉了镜头的位置
+pub trait Renderer {
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color);
+
+    // 一条折线
+    fn stroke_line(&mut self, points: impl Iterator<Item = (f64, f64)>, width: f64, color: Color);
+
+    // 第几号玩家的鸟，中心在(x, y)
+    fn draw_bird(
+        &mut self,
+        player: usize,
+        pose: (Anim, usize),
+        x: f64,
+        y: f64,
+        angle: f64,
+        alpha: f64,
+    );
+}
+
+// 每只鸟这一帧放到哪个动作哪一帧，没有的鸟随便填
+pub fn poses(world: &World, animators: &mut [Animator; 2]) -> [(Anim, usize); 2] {
+    let mut poses = [(Anim::default(), 0); 2];
+    for ((pose, bird), animator) in poses
+        .iter_mut()
+        .zip(world.birds.iter())
+        .zip(animators.iter_mut())
+    {
+        *pose = animator.advance(Anim::of(bird));
+    }
+    poses
+}
+
+pub fn draw_background(r: &mut impl Renderer, world: &World, palette: &Palette) {
+    r.fill_rect(0., 0., world.w, world.h, Color::gray(palette.bg));
+}
+
+// 轨迹、鸟和障碍物，trail_len: 轨迹画多长
+pub fn draw_world(
+    r: &mut impl Renderer,
+    world: &World,
+    poses: &[(Anim, usize); 2],
+    tints: [(i32, i32, i32); 2],
+    palette: &Palette,
+    trail_len: usize,
+) {
+    let dx = world.to_screen_x(0.);
+    for (player, ((bird, tint), pose)) in world.birds.iter().zip(tints).zip(poses).enumerate() {
+        let alpha = if bird.alive { 1. } else { CRASHED_ALPHA };
+
+        // 每隔HISTORY_COLOR_CHANGE个点淡一档，每段接上前一段的最后一个点
+        let len = bird.history.len().min(trail_len);
+        for start in (0..len).step_by(HISTORY_COLOR_CHANGE) {
+            let end = (start + HISTORY_COLOR_CHANGE).min(len);
+            let color = Color::rgb(trail_rgb(tint, start / HISTORY_COLOR_CHANGE)).alpha(alpha);
+            let points = bird
+                .history
+                .range(start.saturating_sub(1)..end)
+                .map(|(x, y)| (x + dx, *y));
+            r.stroke_line(points, TRAIL_WIDTH, color);
+        }
+
+        r.draw_bird(
+            player,
+            *pose,
+            bird.x + dx,
+            world.bird_y(bird),
+            bird.angle,
+            alpha,
+        );
+    }
+
+    let color = Color::gray(palette.obstacle);
+    for ob in world.obstacles.iter() {
+        r.fill_rect(ob.x + dx, 0., OB_WIDTH, ob.y1, color);
+        r.fill_rect(ob.x + dx, ob.y2, OB_WIDTH, world.h - ob.y2, color);
+    }
+}
+
+// 屏幕右边提示下一个障碍物的竖条
+pub fn draw_warning(r: &mut impl Renderer, world: &World, palette: &Palette) {
+    if let Some(ob) = world.next_obstacle() {
+        let (w, h) = (world.w, world.h);
+        let color = Color::gray(palette.next_obstacle);
+        r.fill_rect(w - NEXT_OB_WIDTH, 0., NEXT_OB_WIDTH, ob.y1, color);
+        r.fill_rect(w - NEXT_OB_WIDTH, ob.y2, NEXT_OB_WIDTH, h - ob.y2, color);
+    }
+}
@@ -0,0 +1,280 @@
+// 最近几秒每一帧的样子，撞了以后慢放，也能存成文件拿去别处画
+use std::collections::VecDeque;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{Bird, Obstacle, World, HISTORY_LEN};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BirdFrame {
+    pub x: f64,
+    pub pos: f64,
+    pub angle: f64,
+    pub flying: bool,
+    pub alive: bool,
+    pub score: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Frame {
+    pub birds: Vec<BirdFrame>,
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl Frame {
+    pub fn capture(world: &World) -> Self {
+        let mut frame = Self {
+            birds: vec![],
+            obstacles: vec![],
+        };
+        frame.overwrite(world);
+        frame
+    }
+
+    // 用旧的一帧装新的，不重新分配
+    fn overwrite(&mut self, world: &World) {
+        self.birds.clear();
+        self.birds.extend(world.birds.iter().map(|b| BirdFrame {
+            x: b.x,
+            pos: b.pos,
+            angle: b.angle,
+            flying: b.flying,
+            alive: b.alive,
+            score: b.score,
+        }));
+        self.obstacles.clone_from(&world.obstacles);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub w: f64,
+    pub h: f64,
+    pub frames: Vec<Frame>,
+}
+
+impl Replay {
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // 还原成能画的世界，轨迹由Playback一帧帧攒出来
+    fn world(&self, i: usize, histories: &[VecDeque<(f64, f64)>]) -> World {
+        let frame = &self.frames[i];
+        let birds = frame
+            .birds
+            .iter()
+            .zip(histories)
+            .map(|(bird, history)| Bird {
+                x: bird.x,
+                pos: bird.pos,
+                angle: bird.angle,
+                flying: bird.flying,
+                climb_rate: 1.0,
+                alive: bird.alive,
+                score: bird.score,
+                scored_x: bird.x,
+                history: history.clone(),
+            })
+            .collect();
+        World {
+            w: self.w,
+            h: self.h,
+            birds,
+            obstacles: frame.obstacles.clone(),
+            invincible: false,
+            generated: 0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    // 最后撞上的那只鸟在哪里撞的
+    pub fn impact(&self) -> Option<(f64, f64)> {
+        let last = self.frames.last()?;
+        last.birds
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| !b.alive)
+            .max_by_key(|(b, _)| {
+                self.frames
+                    .iter()
+                    .rposition(|f| f.birds.get(*b).is_some_and(|b| b.alive))
+            })
+            .map(|(_, b)| (b.x, self.h / 2. + b.pos))
+    }
+}
+
+// 从头往后放，轨迹接着上一帧的往下拼，不用每帧把前面的都扫一遍
+// 换了回放要reset
+#[derive(Default)]
+pub struct Playback {
+    // 下一个要拼进轨迹的是第几帧
+    next: usize,
+    histories: Vec<VecDeque<(f64, f64)>>,
+}
+
+impl Playback {
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.histories.clear();
+    }
+
+    // 轨迹是前面几帧活着时的位置，往回跳就从头再拼
+    pub fn world(&mut self, replay: &Replay, i: usize) -> World {
+        if i + 1 < self.next {
+            self.reset();
+        }
+        for frame in &replay.frames[self.next..=i] {
+            if self.histories.len() < frame.birds.len() {
+                self.histories.resize_with(frame.birds.len(), VecDeque::new);
+            }
+            for (history, bird) in self.histories.iter_mut().zip(&frame.birds) {
+                if !bird.alive {
+                    continue;
+                }
+                if history.len() == HISTORY_LEN {
+                    history.pop_back();
+                }
+                history.push_front((bird.x, replay.h / 2. + bird.pos));
+            }
+        }
+        self.next = self.next.max(i + 1);
+        replay.world(i, &self.histories)
+    }
+}
+
+// 只留最近capacity帧
+pub struct Recorder {
+    capacity: usize,
+    w: f64,
+    h: f64,
+    frames: VecDeque<Frame>,
+}
+
+impl Recorder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            w: 0.,
+            h: 0.,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn record(&mut self, world: &World) {
+        if self.frames.len() == self.capacity {
+            let mut frame = self.frames.pop_front().unwrap();
+            frame.overwrite(world);
+            self.frames.push_back(frame);
+        } else {
+            self.frames.push_back(Frame::capture(world));
+        }
+        (self.w, self.h) = (world.w, world.h);
+    }
+
+    pub fn clip(&self) -> Replay {
+        Replay {
+            w: self.w,
+            h: self.h,
+            frames: self.frames.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Autopilot;
+
+    // 自动驾驶飞steps步，每步都录下来
+    fn record(steps: usize, capacity: usize) -> (World, Recorder) {
+        let mut world = World::new(1920., 1080., 1, 4);
+        let mut pilot = Autopilot::default();
+        let mut recorder = Recorder::new(capacity);
+        for _ in 0..steps {
+            world.birds[0].flying = pilot.decide(&world, 0);
+            world.step();
+            recorder.record(&world);
+        }
+        (world, recorder)
+    }
+
+    #[test]
+    fn recorder_keeps_the_latest_frames() {
+        let mut world = World::new(1920., 1080., 1, 4);
+        let mut recorder = Recorder::new(3);
+        let mut xs = vec![];
+        for _ in 0..5 {
+            world.step();
+            recorder.record(&world);
+            xs.push(world.birds[0].x);
+        }
+        let replay = recorder.clip();
+        assert_eq!(replay.len(), 3);
+        let recorded: Vec<f64> = replay.frames.iter().map(|f| f.birds[0].x).collect();
+        assert_eq!(recorded, xs[2..]);
+    }
+
+    #[test]
+    fn history_matches_the_live_world() {
+        let (world, recorder) = record(HISTORY_LEN + 100, HISTORY_LEN + 100);
+        assert!(world.birds[0].alive);
+        let replay = recorder.clip();
+        let last = replay.len() - 1;
+
+        let mut playback = Playback::default();
+        for i in 0..=last {
+            let replayed = playback.world(&replay, i);
+            assert_eq!(replayed.birds[0].history.len(), (i + 1).min(HISTORY_LEN));
+        }
+        // 最新的在前面，和网页上一样
+        let replayed = playback.world(&replay, last);
+        assert_eq!(replayed.birds[0].history, world.birds[0].history);
+
+        // 往回跳也对
+        let back = playback.world(&replay, 10);
+        let fresh = Playback::default().world(&replay, 10);
+        assert_eq!(back.birds[0].history, fresh.birds[0].history);
+        assert_eq!(back.birds[0].history.len(), 11);
+    }
+
+    #[test]
+    fn impact_is_where_the_last_bird_crashed() {
+        for (first, last) in [(0, 1), (1, 0)] {
+            let mut world = World::new(1920., 1080., 2, 4);
+            let mut recorder = Recorder::new(100);
+            for step in 0..30 {
+                world.step();
+                if step == 10 {
+                    assert!(world.birds.iter().all(|b| b.alive));
+                    world.birds[first].alive = false;
+                }
+                if step == 20 {
+                    world.birds[last].alive = false;
+                }
+                recorder.record(&world);
+            }
+            let bird = &world.birds[last];
+            assert_eq!(
+                recorder.clip().impact(),
+                Some((bird.x, world.h / 2. + bird.pos))
+            );
+        }
+    }
+
+    #[test]
+    fn no_impact_while_everyone_is_alive() {
+        let (_, recorder) = record(50, 100);
+        assert_eq!(recorder.clip().impact(), None);
+    }
+}
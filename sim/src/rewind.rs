@@ -0,0 +1,106 @@
+// 倒带用的存档：隔几步存一份整个世界，随机数的状态也在里面，退回去以后障碍物还是原来那些
+use std::collections::VecDeque;
+
+use crate::World;
+
+pub struct Rewind {
+    // 每隔几步存一份
+    every: u32,
+    // 最多留几份，最老的那份就是倒带退回去的地方
+    keep: usize,
+    tick: u32,
+    snapshots: VecDeque<World>,
+}
+
+impl Rewind {
+    // steps: 退回去多少步
+    pub fn new(steps: usize, every: u32) -> Self {
+        let keep = steps / every as usize + 1;
+        Self {
+            every,
+            keep,
+            tick: 0,
+            snapshots: VecDeque::with_capacity(keep),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.tick = 0;
+        self.snapshots.clear();
+    }
+
+    // 只存大家都活着的时候
+    pub fn record(&mut self, world: &World) {
+        if world.birds.iter().any(|b| !b.alive) {
+            return;
+        }
+        if self.tick.is_multiple_of(self.every) {
+            if self.snapshots.len() == self.keep {
+                self.snapshots.pop_front();
+            }
+            self.snapshots.push_back(world.clone());
+        }
+        self.tick += 1;
+    }
+
+    // 退回到最老的那份，分数按撞的时候算，再飞过同样的障碍物不重复加分
+    pub fn rewind(&mut self, crashed: &World) -> Option<World> {
+        let mut world = self.snapshots.pop_front()?;
+        for (bird, old) in world.birds.iter_mut().zip(crashed.birds.iter()) {
+            bird.score = old.score;
+            bird.scored_x = old.scored_x;
+        }
+        self.reset();
+        Some(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::Autopilot;
+
+    #[test]
+    fn rewound_world_replays_the_same_course() {
+        let mut world = World::new(1920., 1080., 1, 7);
+        let mut pilot = Autopilot::default();
+        let mut rewind = Rewind::new(300, 10);
+        // 飞到撞了为止
+        for _ in 0..20000 {
+            world.birds[0].flying = pilot.decide(&world, 0);
+            world.step();
+            rewind.record(&world);
+            if !world.birds[0].alive {
+                break;
+            }
+        }
+        assert!(!world.birds[0].alive);
+
+        let mut back = rewind.rewind(&world).unwrap();
+        assert!(back.birds[0].x < world.birds[0].x);
+        assert_eq!(back.birds[0].score, world.birds[0].score);
+
+        // 飞回原来的位置，障碍物一样，分数不重复算
+        back.invincible = true;
+        let x = world.birds[0].x;
+        while back.birds[0].x < x {
+            back.birds[0].flying = pilot.decide(&back, 0);
+            back.step();
+        }
+        let ahead = |w: &World| {
+            w.obstacles
+                .iter()
+                .filter(|ob| ob.x > x)
+                .map(|ob| (ob.x, ob.y1, ob.y2))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ahead(&back)[0], ahead(&world)[0]);
+        assert_eq!(back.birds[0].score, world.birds[0].score);
+    }
+
+    #[test]
+    fn nothing_to_rewind_before_recording() {
+        let world = World::new(1920., 1080., 1, 7);
+        assert!(Rewind::new(300, 10).rewind(&world).is_none());
+    }
+}
@@ -0,0 +1,193 @@
+// 画面的颜色、皮肤和鸟的动画，网页和本地的工具画出来要一样
+use std::f64::consts::FRAC_PI_2;
+
+use crate::Bird;
+
+// 轨迹每隔几个点淡一档
+pub const HISTORY_COLOR_CHANGE: usize = 15;
+// 屏幕右边提示下一个障碍物的竖条
+pub const NEXT_OB_WIDTH: f64 = 30.;
+
+// 都是灰度
+pub struct Palette {
+    pub bg: u8,
+    pub obstacle: u8,
+    pub next_obstacle: u8,
+}
+
+const NORMAL: Palette = Palette {
+    bg: 240,
+    obstacle: 100,
+    next_obstacle: 190,
+};
+
+const HIGH_CONTRAST: Palette = Palette {
+    bg: 255,
+    obstacle: 0,
+    next_obstacle: 128,
+};
+
+impl Palette {
+    pub fn get(high_contrast: bool) -> &'static Palette {
+        if high_contrast {
+            &HIGH_CONTRAST
+        } else {
+            &NORMAL
+        }
+    }
+}
+
+// tint: 在灰色轨迹上减掉的rgb，age越大越淡
+pub fn trail_rgb((r, g, b): (i32, i32, i32), age: usize) -> (u8, u8, u8) {
+    let c = 255 - age as i32;
+    let channel = |x: i32| (c - x).clamp(0, 255) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+pub struct Skin {
+    pub id: &'static str,
+    // 英文原文，网页上再翻译
+    pub name: &'static str,
+    // 原图是黑白的，用css filter的写法染色
+    pub filter: &'static str,
+    pub tint: (i32, i32, i32),
+}
+
+pub const SKINS: [Skin; 5] = [
+    Skin {
+        id: "classic",
+        name: "Classic",
+        filter: "none",
+        tint: (0, 0, 0),
+    },
+    Skin {
+        id: "robin",
+        name: "Robin",
+        filter: "sepia(1) saturate(5) hue-rotate(-30deg)",
+        tint: (0, 90, 110),
+    },
+    Skin {
+        id: "parrot",
+        name: "Parrot",
+        filter: "sepia(1) saturate(4) hue-rotate(60deg)",
+        tint: (90, 20, 90),
+    },
+    Skin {
+        id: "crow",
+        name: "Crow",
+        filter: "brightness(0.4)",
+        tint: (60, 60, 60),
+    },
+    Skin {
+        id: "canary",
+        name: "Canary",
+        filter: "sepia(1) saturate(3) brightness(1.1)",
+        tint: (20, 40, 120),
+    },
+];
+
+// 2号玩家不能选皮肤，固定用这个
+pub const PLAYER_TWO: Skin = Skin {
+    id: "p2",
+    name: "P2",
+    filter: "sepia(1) saturate(4) hue-rotate(180deg)",
+    tint: (80, 40, 0),
+};
+
+// 两个玩家的鸟各用哪个皮肤，网页和本地画的时候都从这里拿
+pub fn player_skins(p1: &'static Skin) -> [&'static Skin; 2] {
+    [p1, &PLAYER_TWO]
+}
+
+impl Skin {
+    // 找不到就用第一个
+    pub fn by_id(id: &str) -> &'static Skin {
+        SKINS.iter().find(|s| s.id == id).unwrap_or(&SKINS[0])
+    }
+}
+
+// 每隔几步换一帧
+const FLAP_STEPS: u32 = 4;
+const TUMBLE_STEPS: u32 = 6;
+// 扇翅膀时上下压扁的程度，一轮四帧
+const FLAP_SQUASH: [f64; 4] = [1.0, 0.85, 0.7, 0.85];
+// 滑翔时把翅膀稍微展开一点
+const GLIDE_STRETCH: (f64, f64) = (1.05, 0.95);
+const TUMBLE_FRAMES: usize = 4;
+// 图集最宽的一行有几帧
+pub const MAX_FRAMES: usize = 4;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Anim {
+    Flap,
+    #[default]
+    Glide,
+    Tumble,
+}
+
+impl Anim {
+    pub const ALL: [Anim; 3] = [Anim::Flap, Anim::Glide, Anim::Tumble];
+
+    pub fn of(bird: &Bird) -> Self {
+        if !bird.alive {
+            Anim::Tumble
+        } else if bird.flying {
+            Anim::Flap
+        } else {
+            Anim::Glide
+        }
+    }
+
+    // 在图集里的行
+    pub fn row(&self) -> usize {
+        match self {
+            Anim::Flap => 0,
+            Anim::Glide => 1,
+            Anim::Tumble => 2,
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        match self {
+            Anim::Flap => FLAP_SQUASH.len(),
+            Anim::Glide => 1,
+            Anim::Tumble => TUMBLE_FRAMES,
+        }
+    }
+
+    fn steps_per_frame(&self) -> u32 {
+        match self {
+            Anim::Flap => FLAP_STEPS,
+            Anim::Glide => 1,
+            Anim::Tumble => TUMBLE_STEPS,
+        }
+    }
+
+    // 第几帧怎么摆：横向缩放、纵向缩放、旋转
+    pub fn pose(&self, frame: usize) -> (f64, f64, f64) {
+        match self {
+            Anim::Flap => (1., FLAP_SQUASH[frame], 0.),
+            Anim::Glide => (GLIDE_STRETCH.0, GLIDE_STRETCH.1, 0.),
+            Anim::Tumble => (1., 1., frame as f64 * FRAC_PI_2),
+        }
+    }
+}
+
+// 每只鸟放到哪一帧了，状态变了从头放
+#[derive(Default)]
+pub struct Animator {
+    anim: Anim,
+    ticks: u32,
+}
+
+impl Animator {
+    pub fn advance(&mut self, anim: Anim) -> (Anim, usize) {
+        if anim != self.anim {
+            self.anim = anim;
+            self.ticks = 0;
+        }
+        let frame = (self.ticks / anim.steps_per_frame()) as usize % anim.frames();
+        self.ticks += 1;
+        (anim, frame)
+    }
+}
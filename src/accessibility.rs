@@ -0,0 +1,41 @@
+use wasm_bindgen::JsValue;
+use web_sys::AudioContext;
+
+// 嘀的长短和音量
+const CUE_SECONDS: f64 = 0.12;
+const CUE_VOLUME: f32 = 0.3;
+// 越近音越高
+const CUE_BASE_FREQ: f64 = 440.;
+
+// 浏览器要求有过用户操作才能出声，第一次嘀的时候再创建
+#[derive(Default)]
+pub struct ObstacleCue {
+    ctx: Option<AudioContext>,
+}
+
+impl ObstacleCue {
+    // steps_left: 还剩几米，1最近
+    pub fn beep(&mut self, steps_left: u32) {
+        if self.ctx.is_none() {
+            self.ctx = AudioContext::new().ok();
+        }
+        if let Some(ctx) = &self.ctx {
+            let _ = play_tone(ctx, CUE_BASE_FREQ * 2_f64.powf(1. / steps_left as f64));
+        }
+    }
+}
+
+fn play_tone(ctx: &AudioContext, freq: f64) -> Result<(), JsValue> {
+    let osc = ctx.create_oscillator()?;
+    let gain = ctx.create_gain()?;
+    let now = ctx.current_time();
+    osc.frequency().set_value(freq as f32);
+    gain.gain().set_value_at_time(CUE_VOLUME, now)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(0.001, now + CUE_SECONDS)?;
+    osc.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&ctx.destination())?;
+    osc.start()?;
+    osc.stop_with_when(now + CUE_SECONDS)?;
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use yew::{function_component, html, Callback, Html, Properties};
+
+use crate::i18n::{tr, trf};
+use crate::storage::{load_json, save_json};
+
+const STORAGE_KEY: &str = "achievements";
+
+pub enum GameEvent {
+    FlightStarted,
+    PassedObstacle,
+    Crashed,
+    NewBest,
+    Looped,
+    // 不按住时飞过的距离，单位m
+    Glided(f64),
+    Climbed,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Goal {
+    Flights(u32),
+    RunObstacles(u32),
+    TotalObstacles(u32),
+    Crashes(u32),
+    NewBests(u32),
+    Loops(u32),
+    RunLoops(u32),
+    Glide(u32),
+}
+
+#[derive(PartialEq)]
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    goal: Goal,
+}
+
+macro_rules! achievement {
+    ($id:literal, $name:literal, $description:literal, $goal:expr) => {
+        Achievement {
+            id: $id,
+            name: $name,
+            description: $description,
+            goal: $goal,
+        }
+    };
+}
+
+pub const CATALOGUE: [Achievement; 26] = [
+    achievement!(
+        "first_flight",
+        "Hello, sky",
+        "Take off for the first time",
+        Goal::Flights(1)
+    ),
+    achievement!(
+        "flights_50",
+        "Frequent flyer",
+        "Take off 50 times",
+        Goal::Flights(50)
+    ),
+    achievement!(
+        "flights_500",
+        "Sky resident",
+        "Take off 500 times",
+        Goal::Flights(500)
+    ),
+    achievement!(
+        "run_1",
+        "First gap",
+        "Pass an obstacle",
+        Goal::RunObstacles(1)
+    ),
+    achievement!(
+        "run_5",
+        "Warming up",
+        "Pass 5 obstacles in one flight",
+        Goal::RunObstacles(5)
+    ),
+    achievement!(
+        "run_10",
+        "Getting the hang of it",
+        "Pass 10 obstacles in one flight",
+        Goal::RunObstacles(10)
+    ),
+    achievement!(
+        "run_20",
+        "Threading needles",
+        "Pass 20 obstacles in one flight",
+        Goal::RunObstacles(20)
+    ),
+    achievement!(
+        "run_30",
+        "Steady wings",
+        "Pass 30 obstacles in one flight",
+        Goal::RunObstacles(30)
+    ),
+    achievement!(
+        "run_50",
+        "Untouchable",
+        "Pass 50 obstacles in one flight",
+        Goal::RunObstacles(50)
+    ),
+    achievement!(
+        "run_100",
+        "Flying bird",
+        "Pass 100 obstacles in one flight",
+        Goal::RunObstacles(100)
+    ),
+    achievement!(
+        "total_100",
+        "Commuter",
+        "Pass 100 obstacles in total",
+        Goal::TotalObstacles(100)
+    ),
+    achievement!(
+        "total_500",
+        "Migrant",
+        "Pass 500 obstacles in total",
+        Goal::TotalObstacles(500)
+    ),
+    achievement!(
+        "total_1000",
+        "Long haul",
+        "Pass 1000 obstacles in total",
+        Goal::TotalObstacles(1000)
+    ),
+    achievement!(
+        "total_5000",
+        "Around the world",
+        "Pass 5000 obstacles in total",
+        Goal::TotalObstacles(5000)
+    ),
+    achievement!(
+        "crash_1",
+        "Ouch",
+        "Crash for the first time",
+        Goal::Crashes(1)
+    ),
+    achievement!(
+        "crash_100",
+        "Thick feathers",
+        "Crash 100 times",
+        Goal::Crashes(100)
+    ),
+    achievement!(
+        "crash_500",
+        "Never give up",
+        "Crash 500 times",
+        Goal::Crashes(500)
+    ),
+    achievement!(
+        "best_1",
+        "Personal best",
+        "Beat your best score",
+        Goal::NewBests(1)
+    ),
+    achievement!(
+        "best_10",
+        "Always improving",
+        "Beat your best score 10 times",
+        Goal::NewBests(10)
+    ),
+    achievement!("loop_1", "Loop the loop", "Fly a full loop", Goal::Loops(1)),
+    achievement!(
+        "loop_run_3",
+        "Aerobatics",
+        "Fly 3 loops in one flight",
+        Goal::RunLoops(3)
+    ),
+    achievement!("loop_50", "Dizzy", "Fly 50 loops in total", Goal::Loops(50)),
+    achievement!(
+        "glide_10",
+        "Gliding",
+        "Fly 10 m without pressing",
+        Goal::Glide(10)
+    ),
+    achievement!(
+        "glide_30",
+        "Riding the wind",
+        "Fly 30 m without pressing",
+        Goal::Glide(30)
+    ),
+    achievement!(
+        "glide_60",
+        "Hands off",
+        "Fly 60 m without pressing",
+        Goal::Glide(60)
+    ),
+    achievement!(
+        "glide_100",
+        "Albatross",
+        "Fly 100 m without pressing",
+        Goal::Glide(100)
+    ),
+];
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    flights: u32,
+    total_obstacles: u32,
+    crashes: u32,
+    new_bests: u32,
+    loops: u32,
+    best_run_obstacles: u32,
+    best_run_loops: u32,
+    best_glide: u32,
+    unlocked: BTreeSet<String>,
+}
+
+impl Progress {
+    fn value(&self, goal: Goal) -> (u32, u32) {
+        match goal {
+            Goal::Flights(n) => (self.flights, n),
+            Goal::RunObstacles(n) => (self.best_run_obstacles, n),
+            Goal::TotalObstacles(n) => (self.total_obstacles, n),
+            Goal::Crashes(n) => (self.crashes, n),
+            Goal::NewBests(n) => (self.new_bests, n),
+            Goal::Loops(n) => (self.loops, n),
+            Goal::RunLoops(n) => (self.best_run_loops, n),
+            Goal::Glide(n) => (self.best_glide, n),
+        }
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.unlocked.contains(achievement.id)
+    }
+
+    pub fn progress(&self, achievement: &Achievement) -> (u32, u32) {
+        let (value, target) = self.value(achievement.goal);
+        (value.min(target), target)
+    }
+}
+
+#[derive(Default)]
+pub struct AchievementTracker {
+    progress: Progress,
+    run_obstacles: u32,
+    run_loops: u32,
+    run_new_best: bool,
+    glide: f64,
+}
+
+impl AchievementTracker {
+    pub fn load() -> Self {
+        Self {
+            progress: load_json(STORAGE_KEY),
+            ..Default::default()
+        }
+    }
+
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    // 返回新解锁的成就
+    pub fn handle(&mut self, event: GameEvent) -> Vec<&'static Achievement> {
+        let p = &mut self.progress;
+        match event {
+            GameEvent::FlightStarted => {
+                p.flights += 1;
+                self.run_obstacles = 0;
+                self.run_loops = 0;
+                self.run_new_best = false;
+                self.glide = 0.;
+            }
+            GameEvent::PassedObstacle => {
+                self.run_obstacles += 1;
+                p.total_obstacles += 1;
+                p.best_run_obstacles = p.best_run_obstacles.max(self.run_obstacles);
+            }
+            GameEvent::Crashed => p.crashes += 1,
+            // 一局里多次刷新最高分只算一次
+            GameEvent::NewBest => {
+                if !self.run_new_best {
+                    self.run_new_best = true;
+                    p.new_bests += 1;
+                }
+            }
+            GameEvent::Looped => {
+                self.run_loops += 1;
+                p.loops += 1;
+                p.best_run_loops = p.best_run_loops.max(self.run_loops);
+            }
+            GameEvent::Glided(d) => {
+                self.glide += d;
+                p.best_glide = p.best_glide.max(self.glide as u32);
+            }
+            GameEvent::Climbed => self.glide = 0.,
+        }
+
+        let unlocked: Vec<&'static Achievement> = CATALOGUE
+            .iter()
+            .filter(|a| !p.is_unlocked(a))
+            .filter(|a| {
+                let (value, target) = p.value(a.goal);
+                value >= target
+            })
+            .collect();
+        for a in unlocked.iter() {
+            p.unlocked.insert(a.id.to_string());
+        }
+
+        if !unlocked.is_empty() || matches!(event, GameEvent::Crashed) {
+            save_json(STORAGE_KEY, p);
+        }
+        unlocked
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AchievementsScreenProps {
+    pub progress: Progress,
+    pub on_close: Callback<()>,
+}
+
+#[function_component(AchievementsScreen)]
+pub fn achievements_screen(props: &AchievementsScreenProps) -> Html {
+    let AchievementsScreenProps { progress, on_close } = props;
+    let unlocked_cnt = CATALOGUE.iter().filter(|a| progress.is_unlocked(a)).count();
+    let on_close = on_close.reform(|_| ());
+
+    html! {
+        <div class="screen">
+            <div class="screen-header">
+                <h2>{ trf("Achievements {unlocked}/{total}", &[("unlocked", &unlocked_cnt), ("total", &CATALOGUE.len())]) }</h2>
+                <button onclick={on_close}>{ "✕" }</button>
+            </div>
+            <ul class="achievements">
+                { for CATALOGUE.iter().map(|a| {
+                    let (value, target) = progress.progress(a);
+                    let unlocked = progress.is_unlocked(a);
+                    html! {
+                        <li key={a.id} class={if unlocked { "unlocked" } else { "locked" }}>
+                            <div class="achievement-name">{ tr(a.name) }</div>
+                            <div class="achievement-desc">{ tr(a.description) }</div>
+                            <progress value={value.to_string()} max={target.to_string()} />
+                            <span class="achievement-value">{ format!("{value}/{target}") }</span>
+                        </li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,47 @@
+// 在网页的canvas上画，画什么由flying_bird_sim::render决定
+use flying_bird_sim::render::{Color, Renderer};
+use web_sys::CanvasRenderingContext2d;
+
+use crate::skins::{Anim, SpriteSheet};
+
+pub struct CanvasRenderer<'a> {
+    pub ctx: &'a CanvasRenderingContext2d,
+    // 两个玩家的鸟
+    pub sheets: &'a [SpriteSheet; 2],
+}
+
+fn css(color: Color) -> String {
+    format!("rgba({}, {}, {}, {})", color.r, color.g, color.b, color.a)
+}
+
+impl Renderer for CanvasRenderer<'_> {
+    fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: Color) {
+        self.ctx.set_fill_style_str(&css(color));
+        self.ctx.fill_rect(x, y, w, h);
+    }
+
+    fn stroke_line(&mut self, points: impl Iterator<Item = (f64, f64)>, width: f64, color: Color) {
+        self.ctx.set_stroke_style_str(&css(color));
+        self.ctx.set_line_width(width);
+        self.ctx.begin_path();
+        for (x, y) in points {
+            self.ctx.line_to(x, y);
+        }
+        self.ctx.stroke();
+    }
+
+    fn draw_bird(
+        &mut self,
+        player: usize,
+        pose: (Anim, usize),
+        x: f64,
+        y: f64,
+        angle: f64,
+        alpha: f64,
+    ) {
+        self.ctx.save();
+        self.ctx.set_global_alpha(alpha);
+        self.sheets[player].draw(self.ctx, pose, x, y, angle);
+        self.ctx.restore();
+    }
+}
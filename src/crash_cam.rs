@@ -0,0 +1,111 @@
+use flying_bird_sim::replay::{Playback, Replay};
+use flying_bird_sim::World;
+
+use crate::INTERV;
+
+// 留最后3秒
+pub const CLIP_FRAMES: usize = 3000 / INTERV as usize;
+const SLOWDOWN: f64 = 3.;
+const ZOOM: f64 = 2.5;
+// 撞上以后停一会儿再从头放
+const HOLD_FRAMES: f64 = 60.;
+
+pub struct Shot {
+    pub world: World,
+    // 在世界坐标上再做的缩放和平移
+    pub zoom: f64,
+    pub offset: (f64, f64),
+    // 刚好放到撞上的那一帧，世界坐标
+    pub impact: Option<(f64, f64)>,
+}
+
+// 慢放，越接近撞上镜头推得越近，撞的地方慢慢移到画面中间
+// reduced motion时镜头不动，只慢放
+#[derive(Default)]
+pub struct CrashCam {
+    cursor: f64,
+    playback: Playback,
+}
+
+impl CrashCam {
+    pub fn reset(&mut self) {
+        self.cursor = 0.;
+        self.playback.reset();
+    }
+
+    pub fn next(&mut self, replay: &Replay, reduced_motion: bool) -> Option<Shot> {
+        let (ix, iy) = replay.impact()?;
+        let last = replay.len() - 1;
+        let i = (self.cursor as usize).min(last);
+        let world = self.playback.world(replay, i);
+
+        let (zoom, offset) = if reduced_motion {
+            (1., (0., 0.))
+        } else {
+            let t = (self.cursor / last.max(1) as f64).min(1.).powi(2);
+            let zoom = 1. + (ZOOM - 1.) * t;
+            let (cx, cy) = (world.to_screen_x(ix), iy);
+            let target = (cx + (world.w / 2. - cx) * t, cy + (world.h / 2. - cy) * t);
+            (zoom, (target.0 - cx * zoom, target.1 - cy * zoom))
+        };
+
+        let before = self.cursor;
+        self.cursor += 1. / SLOWDOWN;
+        let impact = (before < last as f64 && self.cursor >= last as f64).then_some((ix, iy));
+        if self.cursor > last as f64 + HOLD_FRAMES {
+            self.cursor = 0.;
+        }
+
+        Some(Shot {
+            world,
+            zoom,
+            offset,
+            impact,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flying_bird_sim::replay::Recorder;
+
+    use super::*;
+
+    // 不按就掉到地上撞了
+    fn crash_replay() -> Replay {
+        let mut world = World::new(1920., 1080., 1, 0);
+        let mut recorder = Recorder::new(CLIP_FRAMES);
+        while world.birds[0].alive {
+            world.step();
+            recorder.record(&world);
+        }
+        recorder.clip()
+    }
+
+    fn play(reduced_motion: bool) -> Vec<Shot> {
+        let replay = crash_replay();
+        let mut cam = CrashCam::default();
+        (0..replay.len() * SLOWDOWN as usize)
+            .map(|_| cam.next(&replay, reduced_motion).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn zooms_in_on_the_crash() {
+        let shots = play(false);
+        assert_eq!(shots[0].zoom, 1.);
+        assert_eq!(shots.last().unwrap().zoom, ZOOM);
+        assert!(shots.iter().any(|shot| shot.impact.is_some()));
+    }
+
+    #[test]
+    fn camera_stays_still_with_reduced_motion() {
+        let shots = play(true);
+        assert!(shots
+            .iter()
+            .all(|shot| shot.zoom == 1. && shot.offset == (0., 0.)));
+        // 还是慢放到撞上那一帧
+        assert!(shots.iter().any(|shot| shot.impact.is_some()));
+        assert!(!shots.last().unwrap().world.birds[0].alive);
+    }
+}
@@ -0,0 +1,138 @@
+use std::f64::consts::TAU;
+
+use flying_bird_sim::{BIRD_SIZE, HITBOX};
+use wasm_bindgen::JsCast;
+use web_sys::{
+    window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlInputElement, Url,
+};
+use yew::{function_component, html, use_state, Callback, Event, Html, Properties, TargetCast};
+
+use crate::i18n::tr;
+use crate::storage::{load_json, save_json};
+
+const STORAGE_KEY: &str = "custom_bird";
+// 选了自己的图时settings.skin存这个
+pub const CUSTOM_SKIN: &str = "custom";
+
+// 存的是裁好的png的data url
+pub fn load() -> Option<String> {
+    load_json(STORAGE_KEY)
+}
+
+pub fn save(bird: &Option<String>) {
+    save_json(STORAGE_KEY, bird);
+}
+
+// 取中间的正方形，缩到BIRD_SIZE，圆外透明
+fn crop(img: &HtmlImageElement) -> String {
+    let canvas: HtmlCanvasElement = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("canvas")
+        .unwrap()
+        .unchecked_into();
+    canvas.set_width(BIRD_SIZE as u32);
+    canvas.set_height(BIRD_SIZE as u32);
+    let ctx: CanvasRenderingContext2d = canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+
+    let (w, h) = (img.natural_width() as f64, img.natural_height() as f64);
+    let side = w.min(h);
+    ctx.begin_path();
+    ctx.arc(BIRD_SIZE / 2., BIRD_SIZE / 2., BIRD_SIZE / 2., 0., TAU)
+        .unwrap();
+    ctx.clip();
+    ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+        img,
+        (w - side) / 2.,
+        (h - side) / 2.,
+        side,
+        side,
+        0.,
+        0.,
+        BIRD_SIZE,
+        BIRD_SIZE,
+    )
+    .unwrap();
+    canvas.to_data_url_with_type("image/png").unwrap()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CustomBirdProps {
+    pub current: Option<String>,
+    pub on_save: Callback<Option<String>>,
+    pub on_close: Callback<()>,
+}
+
+// 选图、看裁出来的样子和判定范围
+#[function_component(CustomBird)]
+pub fn custom_bird(props: &CustomBirdProps) -> Html {
+    let CustomBirdProps {
+        current,
+        on_save,
+        on_close,
+    } = props;
+    // 刚选的文件，载入后裁好就不要了
+    let source = use_state(|| None::<String>);
+    let cropped = use_state(|| current.clone());
+
+    let onchange = {
+        let source = source.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                source.set(Url::create_object_url_with_blob(&file).ok());
+            }
+        })
+    };
+    let onload = {
+        let source = source.clone();
+        let cropped = cropped.clone();
+        Callback::from(move |e: Event| {
+            let img = e.target_unchecked_into::<HtmlImageElement>();
+            cropped.set(Some(crop(&img)));
+            let _ = Url::revoke_object_url(&img.src());
+            source.set(None);
+        })
+    };
+
+    // 判定用的轮廓是按原来那只鸟描的，换图也不会变
+    let points = HITBOX
+        .iter()
+        .map(|(x, y)| format!("{},{}", x + BIRD_SIZE / 2., y + BIRD_SIZE / 2.))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    html! {
+        <div class="capture">
+            <input type="file" accept="image/*" {onchange} />
+            if let Some(src) = (*source).clone() {
+                <img style="display: none" {src} {onload} />
+            }
+            <div class="bird-preview">
+                if let Some(src) = (*cropped).clone() {
+                    <img {src} />
+                }
+                <svg viewBox={format!("0 0 {BIRD_SIZE} {BIRD_SIZE}")}>
+                    <polygon {points} />
+                </svg>
+            </div>
+            <p>{ tr("The red outline is where you can be hit. It stays the same whatever picture you use.") }</p>
+            <div>
+                <button
+                    disabled={cropped.is_none()}
+                    onclick={
+                        let cropped = cropped.clone();
+                        on_save.reform(move |_| (*cropped).clone())
+                    }
+                >
+                    { tr("Save") }
+                </button>
+                if current.is_some() {
+                    <button onclick={on_save.reform(|_| None)}>{ tr("Remove") }</button>
+                }
+                <button onclick={on_close.reform(|_| ())}>{ tr("Cancel") }</button>
+            </div>
+        </div>
+    }
+}
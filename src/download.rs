@@ -0,0 +1,32 @@
+use js_sys::Array;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{window, Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+pub fn download_blob(filename: &str, blob: &Blob) {
+    let url = Url::create_object_url_with_blob(blob).unwrap();
+    download_url(filename, &url);
+    Url::revoke_object_url(&url).unwrap();
+}
+
+pub fn download_url(filename: &str, url: &str) {
+    let a = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("a")
+        .unwrap()
+        .dyn_into::<HtmlAnchorElement>()
+        .unwrap();
+    a.set_href(url);
+    a.set_download(filename);
+    a.click();
+}
+
+pub fn download_text(filename: &str, mime: &str, content: &str) {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob =
+        Blob::new_with_str_sequence_and_options(&Array::of1(&JsValue::from_str(content)), &options)
+            .unwrap();
+    download_blob(filename, &blob);
+}
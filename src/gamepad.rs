@@ -0,0 +1,65 @@
+use wasm_bindgen::JsCast;
+use web_sys::{window, Gamepad, GamepadButton, GamepadMappingType};
+
+// standard mapping里的按键编号
+// https://w3c.github.io/gamepad/#remapping
+const LEFT_TRIGGER: u32 = 6;
+const RIGHT_TRIGGER: u32 = 7;
+const START: u32 = 9;
+const PRESS_THRESHOLD: f64 = 0.1;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct GamepadState {
+    pub fly: bool,
+    // 只按了扳机时扳机按下的程度，用来调节爬升速度
+    pub trigger: Option<f64>,
+    pub start: bool,
+}
+
+// 返回(是否按了扳机以外的键, 扳机按下的程度)
+fn read_pad(pad: &Gamepad, state: &mut GamepadState) -> (bool, Option<f64>) {
+    let standard = pad.mapping() == GamepadMappingType::Standard;
+    let mut other_pressed = false;
+    let mut trigger: Option<f64> = None;
+    for (i, button) in pad.buttons().iter().enumerate() {
+        let i = i as u32;
+        let button = button.unchecked_into::<GamepadButton>();
+        let pressed = button.pressed() || button.value() > PRESS_THRESHOLD;
+        if !pressed {
+            continue;
+        }
+        if standard && i == START {
+            state.start = true;
+        } else if standard && (i == LEFT_TRIGGER || i == RIGHT_TRIGGER) {
+            trigger = Some(trigger.unwrap_or(0.0).max(button.value()));
+        } else {
+            other_pressed = true;
+        }
+    }
+    (other_pressed, trigger)
+}
+
+// 所有手柄合在一起看
+pub fn poll() -> GamepadState {
+    let mut state = GamepadState::default();
+    let Ok(pads) = window().unwrap().navigator().get_gamepads() else {
+        return state;
+    };
+    let mut other_pressed = false;
+    for pad in pads.iter() {
+        if let Ok(pad) = pad.dyn_into::<Gamepad>() {
+            if pad.connected() {
+                let (other, trigger) = read_pad(&pad, &mut state);
+                other_pressed |= other;
+                if let Some(t) = trigger {
+                    state.trigger = Some(state.trigger.unwrap_or(0.0).max(t));
+                }
+            }
+        }
+    }
+    state.fly = other_pressed || state.trigger.is_some();
+    if other_pressed {
+        state.trigger = None;
+    }
+    state
+}
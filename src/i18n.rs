@@ -0,0 +1,130 @@
+use std::cell::Cell;
+use std::fmt::Display;
+
+use js_sys::{Date, Number};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use web_sys::window;
+
+mod zh_cn;
+
+const FEET_PER_METER: f64 = 3.28084;
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    // 跟着浏览器
+    #[default]
+    Auto,
+    En,
+    ZhCn,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::Auto, Language::En, Language::ZhCn];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Language::Auto => "auto",
+            Language::En => "en",
+            Language::ZhCn => "zh-CN",
+        }
+    }
+
+    // 语言名用它自己的文字写，选错了也认得回来
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Auto => tr("Auto"),
+            Language::En => "English",
+            Language::ZhCn => "简体中文",
+        }
+    }
+
+    fn locale(&self) -> Locale {
+        match self {
+            Language::Auto => {
+                let lang = window().unwrap().navigator().language().unwrap_or_default();
+                if lang.to_lowercase().starts_with("zh") {
+                    Locale::ZhCn
+                } else {
+                    Locale::En
+                }
+            }
+            Language::En => Locale::En,
+            Language::ZhCn => Locale::ZhCn,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Locale {
+    En,
+    ZhCn,
+}
+
+impl Locale {
+    fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+}
+
+thread_local! {
+    static LOCALE: Cell<Locale> = const { Cell::new(Locale::En) };
+    static IMPERIAL: Cell<bool> = const { Cell::new(false) };
+}
+
+// 每次渲染前按设置更新，下面的函数都用这里定下来的语言
+pub fn configure(language: Language, imperial: bool) {
+    let locale = language.locale();
+    if LOCALE.replace(locale) != locale {
+        if let Some(html) = window()
+            .unwrap()
+            .document()
+            .and_then(|doc| doc.document_element())
+        {
+            let _ = html.set_attribute("lang", locale.tag());
+        }
+    }
+    IMPERIAL.set(imperial);
+}
+
+// 原文就是英文目录，找不到翻译就用原文
+pub fn tr(msg: &'static str) -> &'static str {
+    match LOCALE.get() {
+        Locale::En => msg,
+        Locale::ZhCn => zh_cn::translate(msg).unwrap_or(msg),
+    }
+}
+
+// 翻译后把{name}换成对应的值
+pub fn trf(msg: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(tr(msg).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+// 按语言加千位分隔符
+pub fn format_number(n: f64) -> String {
+    Number::from(n).to_locale_string(LOCALE.get().tag()).into()
+}
+
+pub fn format_distance(meters: f64) -> String {
+    if IMPERIAL.get() {
+        format!(
+            "{} {}",
+            format_number((meters * FEET_PER_METER).round()),
+            tr("ft")
+        )
+    } else {
+        format!("{} {}", format_number(meters.round()), tr("m"))
+    }
+}
+
+pub fn format_date(time: f64) -> String {
+    Date::new(&JsValue::from_f64(time))
+        .to_locale_date_string(LOCALE.get().tag(), &JsValue::UNDEFINED)
+        .into()
+}
@@ -0,0 +1,189 @@
+// 简体中文，左边是代码里的英文原文
+pub fn translate(msg: &str) -> Option<&'static str> {
+    Some(match msg {
+        // 单位
+        "m" => "米",
+        "ft" => "英尺",
+
+        // 标题画面和游戏中
+        "Tap to fly" => "点击屏幕起飞",
+        "Click or press {key} to fly" => "点击或按{key}起飞",
+        "Click to fly" => "点击起飞",
+        "P1: {p1} · P2: {p2}" => "P1：{p1} · P2：{p2}",
+        "{key} or left half" => "{key}或屏幕左半边",
+        "left half" => "屏幕左半边",
+        "{key} or right half" => "{key}或屏幕右半边",
+        "right half" => "屏幕右半边",
+        "Waiting for other players…" => "等其他人准备好…",
+        "Fly when you are ready" => "准备好了就飞",
+        "Join a room to race" => "加入一个房间开始比赛",
+        "Paused" => "暂停",
+        "Fly to go back 3 seconds, or press {key} to start over" => {
+            "起飞就退回3秒前，按{key}从头开始"
+        }
+        "Fly to go back 3 seconds" => "起飞就退回3秒前",
+        "New best score!" => "新纪录！",
+        "Save replay" => "保存回放",
+        "Score card" => "成绩卡",
+        "Best" => "最高分",
+        "Seed {seed}" => "种子 {seed}",
+        "Download" => "下载",
+        "Share" => "分享",
+        "Score {score}, {lives} lives." => "得分{score}，还剩{lives}条命。",
+        "{player} score {score}, {lives} lives." => "{player}得分{score}，还剩{lives}条命。",
+        "🎮 Connected" => "🎮 已连接",
+        "🎮 Disconnected" => "🎮 已断开",
+
+        // 联机
+        "Invalid server address" => "服务器地址不对",
+        "Disconnected" => "已断开",
+        "Connection lost ({code})" => "连接断了（{code}）",
+        "Room \"{room}\"" => "房间「{room}」",
+        " (you)" => "（你）",
+        "Last round: " => "上一局：",
+        "Leave" => "离开",
+
+        // 练习
+        "Practice" => "练习",
+        "Crashes are free here and nothing is recorded." => "这里撞了也不会死，成绩不会记下来。",
+        "Course" => "赛道",
+        "Start at obstacle" => "从第几个障碍物开始",
+        "Loop every (obstacles, 0 = off)" => "飞过几个障碍物就重来（0是不重来）",
+        "Game speed {speed}%" => "游戏速度 {speed}%",
+        "Stop practice" => "不练了",
+        "Server" => "服务器",
+        "Room" => "房间",
+        "Name" => "名字",
+        "Connecting…" => "连接中…",
+        "Join" => "加入",
+
+        // 设置
+        "Settings" => "设置",
+        "Skin" => "皮肤",
+        "Classic" => "经典",
+        "Robin" => "知更鸟",
+        "Parrot" => "鹦鹉",
+        "Crow" => "乌鸦",
+        "Canary" => "金丝雀",
+        "My bird" => "我的鸟",
+        "Upload" => "上传",
+        "The red outline is where you can be hit. It stays the same whatever picture you use." => {
+            "红线以内才算撞到，换什么图都不变。"
+        }
+        "Save" => "保存",
+        "Remove" => "删除",
+        "Low quality graphics" => "低画质",
+        "Language" => "语言",
+        "Auto" => "跟随浏览器",
+        "Show distances in feet" => "距离用英尺显示",
+        "Gamepad" => "手柄",
+        "Rules" => "规则",
+        "Classic: start over after a crash" => "经典：撞了从头开始",
+        "Rewind: spend a life to go back 3 seconds" => "倒带：花一条命退回3秒前",
+        "Analog triggers control climb rate" => "扳机按下的程度控制爬升速度",
+        "Sound" => "声音",
+        "Mute" => "静音",
+        "Accessibility" => "无障碍",
+        "Game speed " => "游戏速度 ",
+        "Reduced motion" => "减少动效",
+        "High contrast" => "高对比度",
+        "Beep before obstacles" => "障碍物快到时提示音",
+        "Press once to start climbing, again to stop" => "按一下开始爬升，再按一下停止",
+        "Controls" => "按键",
+        "Reset controls" => "恢复默认按键",
+        "Press a key, click or tap here to bind \"{action}\"" => {
+            "按一个键，或者在这里点击，绑定到「{action}」"
+        }
+        "Cancel" => "取消",
+        "Fly" => "飞",
+        "Fly (player 1)" => "飞（玩家1）",
+        "Fly (player 2)" => "飞（玩家2）",
+        "Pause" => "暂停",
+        "Restart" => "重新开始",
+        "Toggle debug" => "调试信息",
+        "Left click" => "鼠标左键",
+        "Middle click" => "鼠标中键",
+        "Right click" => "鼠标右键",
+        "Mouse {button}" => "鼠标键{button}",
+        "Touch" => "触摸",
+
+        // 统计和排行榜
+        "Statistics" => "统计",
+        "Flights" => "飞行次数",
+        "Crashes into {cause}" => "撞到{cause}",
+        "Total distance" => "总距离",
+        "Time airborne" => "飞行时间",
+        "Average score" => "平均得分",
+        "Median score" => "得分中位数",
+        "Score history" => "历史得分",
+        "Crashes" => "撞到了哪里",
+        "Export CSV" => "导出CSV",
+        "Export JSON" => "导出JSON",
+        "ceiling" => "天花板",
+        "floor" => "地面",
+        "top pipe" => "上面的柱子",
+        "bottom pipe" => "下面的柱子",
+        "left behind" => "掉队",
+        "Leaderboard" => "排行榜",
+        "Score" => "得分",
+        "Distance" => "距离",
+        "Style" => "擦边",
+
+        // 成就
+        "Achievements {unlocked}/{total}" => "成就 {unlocked}/{total}",
+        "Hello, sky" => "你好，天空",
+        "Take off for the first time" => "第一次起飞",
+        "Frequent flyer" => "常客",
+        "Take off 50 times" => "起飞50次",
+        "Sky resident" => "天空居民",
+        "Take off 500 times" => "起飞500次",
+        "First gap" => "第一道缝",
+        "Pass an obstacle" => "穿过一个障碍物",
+        "Warming up" => "热身",
+        "Pass 5 obstacles in one flight" => "一次飞行穿过5个障碍物",
+        "Getting the hang of it" => "找到感觉了",
+        "Pass 10 obstacles in one flight" => "一次飞行穿过10个障碍物",
+        "Threading needles" => "穿针引线",
+        "Pass 20 obstacles in one flight" => "一次飞行穿过20个障碍物",
+        "Steady wings" => "稳如泰山",
+        "Pass 30 obstacles in one flight" => "一次飞行穿过30个障碍物",
+        "Untouchable" => "碰不到我",
+        "Pass 50 obstacles in one flight" => "一次飞行穿过50个障碍物",
+        "Flying bird" => "飞翔的鸟",
+        "Pass 100 obstacles in one flight" => "一次飞行穿过100个障碍物",
+        "Commuter" => "通勤",
+        "Pass 100 obstacles in total" => "一共穿过100个障碍物",
+        "Migrant" => "候鸟",
+        "Pass 500 obstacles in total" => "一共穿过500个障碍物",
+        "Long haul" => "长途飞行",
+        "Pass 1000 obstacles in total" => "一共穿过1000个障碍物",
+        "Around the world" => "环游世界",
+        "Pass 5000 obstacles in total" => "一共穿过5000个障碍物",
+        "Ouch" => "哎哟",
+        "Crash for the first time" => "第一次撞上",
+        "Thick feathers" => "皮糙肉厚",
+        "Crash 100 times" => "撞100次",
+        "Never give up" => "永不放弃",
+        "Crash 500 times" => "撞500次",
+        "Personal best" => "个人纪录",
+        "Beat your best score" => "刷新最高分",
+        "Always improving" => "天天向上",
+        "Beat your best score 10 times" => "刷新最高分10次",
+        "Loop the loop" => "翻个跟头",
+        "Fly a full loop" => "飞一整圈",
+        "Aerobatics" => "特技飞行",
+        "Fly 3 loops in one flight" => "一次飞行转3圈",
+        "Dizzy" => "头晕",
+        "Fly 50 loops in total" => "一共转50圈",
+        "Gliding" => "滑翔",
+        "Fly 10 m without pressing" => "不按着飞10米",
+        "Riding the wind" => "乘风",
+        "Fly 30 m without pressing" => "不按着飞30米",
+        "Hands off" => "放手",
+        "Fly 60 m without pressing" => "不按着飞60米",
+        "Albatross" => "信天翁",
+        "Fly 100 m without pressing" => "不按着飞100米",
+
+        _ => return None,
+    })
+}
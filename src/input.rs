@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use web_sys::{KeyboardEvent, PointerEvent};
+
+use crate::i18n::{tr, trf};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Fly,
+    // 双人模式各自的键，单人模式时也能用来飞
+    Player1Fly,
+    Player2Fly,
+    Pause,
+    Restart,
+    Mute,
+    ToggleDebug,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::Fly,
+        Action::Player1Fly,
+        Action::Player2Fly,
+        Action::Pause,
+        Action::Restart,
+        Action::Mute,
+        Action::ToggleDebug,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        tr(match self {
+            Action::Fly => "Fly",
+            Action::Player1Fly => "Fly (player 1)",
+            Action::Player2Fly => "Fly (player 2)",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::Mute => "Mute",
+            Action::ToggleDebug => "Toggle debug",
+        })
+    }
+}
+
+// 可以绑定到动作上的输入
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Binding {
+    // KeyboardEvent.code，和键盘布局无关
+    Key(String),
+    // PointerEvent.button
+    Mouse(i16),
+    Touch,
+}
+
+impl Binding {
+    pub fn from_key(e: &KeyboardEvent) -> Option<Self> {
+        // 带修饰键的是浏览器快捷键，不管
+        if e.ctrl_key() || e.meta_key() || e.alt_key() {
+            return None;
+        }
+        Some(Binding::Key(e.code()))
+    }
+
+    pub fn from_pointer(e: &PointerEvent) -> Self {
+        if e.pointer_type() == "touch" {
+            Binding::Touch
+        } else {
+            Binding::Mouse(e.button())
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(code) => code
+                .strip_prefix("Key")
+                .or_else(|| code.strip_prefix("Digit"))
+                .unwrap_or(code)
+                .to_string(),
+            Binding::Mouse(0) => tr("Left click").to_string(),
+            Binding::Mouse(1) => tr("Middle click").to_string(),
+            Binding::Mouse(2) => tr("Right click").to_string(),
+            Binding::Mouse(b) => trf("Mouse {button}", &[("button", b)]),
+            Binding::Touch => tr("Touch").to_string(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = |code: &str| Binding::Key(code.to_string());
+        Self(BTreeMap::from([
+            (
+                Action::Fly,
+                vec![
+                    key("Space"),
+                    key("ArrowUp"),
+                    key("KeyW"),
+                    key("Enter"),
+                    Binding::Mouse(0),
+                    Binding::Touch,
+                ],
+            ),
+            (Action::Player1Fly, vec![key("KeyA")]),
+            (Action::Player2Fly, vec![key("KeyL")]),
+            (Action::Pause, vec![key("KeyP"), key("Escape")]),
+            (Action::Restart, vec![key("KeyR")]),
+            (Action::Mute, vec![key("KeyM")]),
+            (Action::ToggleDebug, vec![key("Backquote")]),
+        ]))
+    }
+}
+
+impl Bindings {
+    pub fn action_for(&self, binding: &Binding) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(_, bindings)| bindings.contains(binding))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // 一个输入只能绑定一个动作
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        for bindings in self.0.values_mut() {
+            bindings.retain(|b| *b != binding);
+        }
+        self.0.entry(action).or_default().push(binding);
+    }
+
+    // 旧版本存下来的绑定里没有新加的动作，补上默认的
+    pub fn fill_missing(&mut self) {
+        for (action, bindings) in Bindings::default().0 {
+            if self.0.contains_key(&action) {
+                continue;
+            }
+            let free: Vec<Binding> = bindings
+                .into_iter()
+                .filter(|b| self.action_for(b).is_none())
+                .collect();
+            self.0.insert(action, free);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, binding: &Binding) {
+        if let Some(bindings) = self.0.get_mut(&action) {
+            bindings.retain(|b| b != binding);
+        }
+    }
+}
+
+// 具体是哪个键/哪根手指按着
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Key(String),
+    Pointer(i32),
+    Gamepad,
+}
+
+// 正在按着fly的所有输入，全部松开才算松开
+#[derive(Default)]
+pub struct HeldSources(BTreeSet<Source>);
+
+impl HeldSources {
+    // 返回是否从没有按着变成按着
+    pub fn press(&mut self, source: Source) -> bool {
+        let was_empty = self.0.is_empty();
+        self.0.insert(source);
+        was_empty
+    }
+
+    // 返回是否全部松开了
+    pub fn release(&mut self, source: &Source) -> bool {
+        self.0.remove(source) && self.0.is_empty()
+    }
+
+    pub fn release_all(&mut self) -> bool {
+        let was_empty = self.0.is_empty();
+        self.0.clear();
+        !was_empty
+    }
+}
@@ -0,0 +1,92 @@
+use yew::{function_component, html, use_state, Callback, Html, Properties};
+
+use crate::i18n::{format_date, format_distance, tr};
+use crate::stats::{load_runs, RunRecord};
+
+const TOP_N: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Category {
+    Score,
+    Distance,
+    Style,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Score, Category::Distance, Category::Style];
+
+    pub fn name(&self) -> &'static str {
+        tr(match self {
+            Category::Score => "Score",
+            Category::Distance => "Distance",
+            Category::Style => "Style",
+        })
+    }
+
+    fn value(&self, run: &RunRecord) -> f64 {
+        match self {
+            Category::Score => run.score as f64,
+            Category::Distance => run.distance,
+            Category::Style => run.style_score as f64,
+        }
+    }
+
+    fn format(&self, run: &RunRecord) -> String {
+        match self {
+            Category::Score => format!("{:0>4}", run.score),
+            Category::Distance => format_distance(run.distance.floor()),
+            Category::Style => format!("✦ {}", run.style_score),
+        }
+    }
+}
+
+pub fn top_runs(runs: &[RunRecord], category: Category) -> Vec<&RunRecord> {
+    let mut sorted: Vec<&RunRecord> = runs.iter().collect();
+    sorted.sort_by(|a, b| category.value(b).total_cmp(&category.value(a)));
+    sorted.truncate(TOP_N);
+    sorted
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LeaderboardScreenProps {
+    pub on_close: Callback<()>,
+}
+
+#[function_component(LeaderboardScreen)]
+pub fn leaderboard_screen(props: &LeaderboardScreenProps) -> Html {
+    let category = use_state(|| Category::Score);
+    let runs = load_runs();
+
+    html! {
+        <div class="screen">
+            <div class="screen-header">
+                <h2>{ tr("Leaderboard") }</h2>
+                <button onclick={props.on_close.reform(|_| ())}>{ "✕" }</button>
+            </div>
+            <div class="tabs">
+                { for Category::ALL.iter().map(|c| {
+                    let c = *c;
+                    let onclick = {
+                        let category = category.clone();
+                        Callback::from(move |_| category.set(c))
+                    };
+                    html! {
+                        <button class={if *category == c { "active" } else { "" }} {onclick}>
+                            { c.name() }
+                        </button>
+                    }
+                }) }
+            </div>
+            <ol class="leaderboard">
+                { for top_runs(&runs, *category).into_iter().map(|run| html! {
+                    <li>
+                        <span class="leaderboard-value">{ category.format(run) }</span>
+                        <span class="leaderboard-date">
+                            { format_date(run.time) }
+                        </span>
+                    </li>
+                }) }
+            </ol>
+        </div>
+    }
+}
@@ -1,45 +1,101 @@
-use std::iter;
+use std::collections::BTreeMap;
+use std::rc::Rc;
 
-use gloo_utils::format::JsValueSerdeExt;
+use flying_bird_protocol::{BirdState, ClientMessage, ServerMessage};
+use flying_bird_sim::bot::Autopilot;
+use flying_bird_sim::render::{self, draw_background, draw_warning, draw_world, Color, Renderer};
+use flying_bird_sim::replay::{Recorder, Replay};
+use flying_bird_sim::rewind::Rewind;
+use flying_bird_sim::theme::{player_skins, trail_rgb, Palette};
+use flying_bird_sim::{SimEvent, World, BIRD_SIZE, HISTORY_LEN, OB_WIDTH};
+use js_sys::Date;
 use rand::{thread_rng, Rng};
-use serde::Serialize;
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use web_sys::{
-    console, window, CanvasRenderingContext2d, Event, HtmlAudioElement, HtmlCanvasElement,
-    HtmlImageElement,
+    console, window, AddEventListenerOptions, CanvasRenderingContext2d, Element, Event,
+    GamepadEvent, HtmlAudioElement, HtmlCanvasElement, HtmlImageElement, KeyboardEvent, MouseEvent,
+    PointerEvent,
 };
 use yew::{
-    callback::Callback, function_component, html, use_effect_with, use_memo, use_node_ref,
-    use_state, Html, TargetCast,
+    callback::Callback, classes, function_component, html, use_effect_with, use_memo, use_mut_ref,
+    use_node_ref, use_reducer, use_reducer_eq, use_state, use_state_eq, Html, TargetCast,
 };
 use yew_hooks::use_interval;
 
-const BG_COLOR: u8 = 240;
-const OB_COLOR: u8 = 100;
-const NEXT_OB_COLOR: u8 = 190;
+mod accessibility;
+mod achievements;
+mod canvas;
+mod crash_cam;
+mod custom_bird;
+mod download;
+mod gamepad;
+mod i18n;
+mod input;
+mod leaderboard;
+mod online;
+mod particles;
+mod players;
+mod practice;
+mod score_card;
+mod settings;
+mod skins;
+mod stats;
+mod storage;
+mod style;
+mod toast;
+
+use accessibility::ObstacleCue;
+use achievements::{AchievementTracker, AchievementsScreen, GameEvent};
+use canvas::CanvasRenderer;
+use crash_cam::{CrashCam, CLIP_FRAMES};
+use download::download_text;
+use gamepad::GamepadState;
+use i18n::{format_distance, tr, trf};
+use input::{Action, Binding, HeldSources, Source};
+use leaderboard::LeaderboardScreen;
+use online::{
+    Ghost, NetClient, NetEvent, Online, OnlineAction, OnlineConfig, OnlinePanel, Status,
+    SEND_EVERY, WORLD_H,
+};
+use particles::Particles;
+use players::{Mode, PLAYERS};
+use practice::{PracticeConfig, PracticePanel, FLASH_STEPS, HIT_FLASH};
+use score_card::{challenge_seed, challenge_url, CardInfo, ScoreCardScreen};
+use settings::{Rules, Settings, SettingsScreen};
+use skins::{Anim, Animator, Skin, SpriteSheet};
+use stats::{add_run, RunTracker, StatsScreen};
+use style::StyleTracker;
+use toast::{ToastAction, Toasts};
 
-const BIRD_SIZE: f64 = 128.;
 const H_BIRD_RATIO: f64 = 8.0;
 const MIN_W_BIRD_RATIO: f64 = 6.0;
 const MAX_BIRD_PX: f64 = 96.;
-const CHECK_SIZE: f64 = BIRD_SIZE / 2.0 + 5.0;
-const OB_WIDTH: f64 = 100.;
-const NEXT_OB_WIDTH: f64 = 30.;
+// 鸟的图按边长从小到大排，高分屏用大的
+const BIRD_SPRITES: [(u32, &str); 3] = [
+    (128, "static/bird.webp"),
+    (256, "static/bird@2x.webp"),
+    (384, "static/bird@3x.webp"),
+];
 
-const HISTORY_LEN: usize = 250;
-const HISTORY_COLOR_CHANGE: usize = 15;
+// reduced motion时只画这么长的轨迹
+const REDUCED_HISTORY_LEN: usize = 30;
+// 下一个障碍物离屏幕还有几米时开始嘀
+const CUE_FROM: u32 = 3;
+// 自动驾驶的计划每隔几步画一个点，颜色和很旧的轨迹一样深
+const PATH_DOT_EVERY: usize = 4;
+const PATH_SHADE: usize = 100;
 
-const MIN_SPACE: f64 = 3. * BIRD_SIZE;
 const INTERV: u32 = 8;
-const V_MIN_2: f64 = 9.0;
-const V_MAX_2: f64 = 81.0;
-const ROTATE_UP: f64 = -0.022;
-const ROTATE_DOWN_D: f64 = 0.12;
 
 const N_LIFES: i32 = 10;
+// 倒带退回3秒前，隔几步存一份
+const REWIND_STEPS: usize = (3000 / INTERV) as usize;
+const REWIND_EVERY: u32 = 10;
 const RESTART_WATING_TIME: u32 = 1500;
+// 标题画面放着不动这么久就自己飞给人看
+const ATTRACT_AFTER: u32 = 10000;
 
 macro_rules! clone_all {
     [$($s:ident), *] => {
@@ -49,41 +105,27 @@ macro_rules! clone_all {
     };
 }
 
-macro_rules! make_cb {
-    ($core:expr) => {{
-        let core = $core.clone();
-        Callback::from(move |_| core())
-    }};
-}
-
-#[derive(Serialize)]
-#[serde(rename_all(serialize = "camelCase"))]
-struct ContextOtions {
-    pub will_read_frequently: bool,
-}
-
-struct Obstacle {
-    x: f64,
-    y1: f64,
-    y2: f64,
-}
-
 struct MapConfig {
+    // 世界的大小，鸟是BIRD_SIZE
     w: f64,
     h: f64,
+    // 算的时候的devicePixelRatio，变了要重新算
+    dpr: f64,
+    // 一个世界单位对应几个物理像素
+    pixel_ratio: f64,
 }
 
 impl MapConfig {
     pub fn calc() -> Self {
-        // 鸟的大小在canvas里面128以免太糊
-        // 鸟的大小是屏幕高度的1/8，但不要超过200px
+        // 鸟的大小是屏幕高度的1/8，但不要超过96px
         // 也不要超过屏幕宽度的1/6
-        // 不去纠结devicePixelRatio了，反正鸟的清晰度有限
+        // canvas按物理像素开，只影响清晰度，不影响世界的大小
 
         console::log_1(&JsValue::from_str("calc map size"));
 
         let screen_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
         let screen_height = window().unwrap().inner_height().unwrap().as_f64().unwrap();
+        let dpr = window().unwrap().device_pixel_ratio();
 
         let h = (BIRD_SIZE * H_BIRD_RATIO)
             .max(screen_height * BIRD_SIZE / MAX_BIRD_PX)
@@ -92,43 +134,74 @@ impl MapConfig {
         Self {
             h,
             w: h * screen_width / screen_height,
+            dpr,
+            pixel_ratio: screen_height * dpr / h,
         }
     }
+
+    fn resize_canvas(&self, canvas: &HtmlCanvasElement) {
+        canvas.set_width((self.w * self.pixel_ratio).round() as u32);
+        canvas.set_height((self.h * self.pixel_ratio).round() as u32);
+    }
+
+    // 够用的最小的那张鸟的图
+    fn bird_sprite(&self) -> &'static str {
+        let need = BIRD_SIZE * self.pixel_ratio;
+        BIRD_SPRITES
+            .iter()
+            .find(|(size, _)| *size as f64 >= need)
+            .or(BIRD_SPRITES.last())
+            .map(|(_, src)| *src)
+            .unwrap()
+    }
 }
 
-impl Obstacle {
-    pub fn random_gen(last: Option<&Obstacle>, w: f64, h: f64, score: u32) -> Self {
-        let mut rng = thread_rng();
-        let dis = rng.gen_range(0.0..(6.0 - score as f64).max(4.0) * OB_WIDTH)
-            + (3.0 - score as f64).max(0.0) * OB_WIDTH;
-        let last_y1 = last.map(|ob| ob.y1).unwrap_or(h / 3.0);
-
-        let space = rng.gen_range(MIN_SPACE..1.5 * MIN_SPACE);
-        let max_dy = (dis / OB_WIDTH).powi(2) * BIRD_SIZE;
-        let y1 = rng.gen_range(
-            (last_y1 - max_dy).max(0.0).min(h - space - 1.0)..(last_y1 + max_dy).min(h - space),
-        );
+// 联机时世界高度固定，宽度按屏幕比例
+fn world_size(mode: Mode, map_config: &MapConfig) -> (f64, f64) {
+    match mode {
+        Mode::Online => (map_config.w * WORLD_H / map_config.h, WORLD_H),
+        _ => (map_config.w, map_config.h),
+    }
+}
 
-        Self {
-            x: w + dis + 2.0 * OB_WIDTH,
-            y1,
-            y2: y1 + space,
+// 界面上显示的数，每帧从世界里抄一份，变了才重画
+#[derive(Clone, Default, PartialEq)]
+struct Hud {
+    scores: [u32; 2],
+    alive: [bool; 2],
+    // 单位m
+    distance: u32,
+    // 联机时对手的(id, 得分)
+    opponents: Vec<(u32, u32)>,
+}
+
+impl Hud {
+    fn of(world: &World, ghosts: &BTreeMap<u32, Ghost>) -> Self {
+        let mut hud = Hud {
+            distance: (world.birds[0].x / 100.0) as u32,
+            opponents: ghosts.iter().map(|(id, g)| (*id, g.score())).collect(),
+            ..Default::default()
+        };
+        for (i, bird) in world.birds.iter().enumerate() {
+            hud.scores[i] = bird.score;
+            hud.alive[i] = bird.alive;
         }
+        hud
     }
 }
 
-fn get_best_score() -> u32 {
+fn get_best(key: &str) -> u32 {
     let storage = window().unwrap().local_storage().unwrap().unwrap();
     storage
-        .get_item("best_score")
+        .get_item(key)
         .unwrap()
         .and_then(|s| s.parse().ok())
         .unwrap_or(0)
 }
 
-fn set_best_score(score: u32) {
+fn set_best(key: &str, value: u32) {
     let storage = window().unwrap().local_storage().unwrap().unwrap();
-    storage.set_item("best_score", &score.to_string()).unwrap();
+    storage.set_item(key, &value.to_string()).unwrap();
 }
 
 fn enter_fullscreen() {
@@ -151,26 +224,164 @@ fn app() -> Html {
 
     let map_config = use_state(MapConfig::calc);
     let canvas_ctx = use_state(|| None);
-    let bird_image = use_state(|| None);
-    let angle = use_state(|| 0.);
-    let pos = use_state(|| 0.);
-    let is_flying = use_state(|| false);
-    let history = use_state(Vec::<(f64, f64)>::new);
-    let obstacles = use_state(Vec::<Obstacle>::new);
+    let bird_image = use_state(|| None::<HtmlImageElement>);
+    // 换图片时还是同一个img元素，用载入次数区分，两张图载入都算
+    let bird_loads = use_state(|| 0_u32);
+    // 2号玩家和对手总是用原来的鸟，不管1号换了什么图
+    let stock_bird_image = use_state(|| None::<HtmlImageElement>);
+    let custom_bird = use_state(custom_bird::load);
+    // 按皮肤染好色的动画帧，图片载入或者换皮肤时重画
+    let sprite_sheets = use_state(|| None::<Rc<[SpriteSheet; 2]>>);
+    let animators = use_mut_ref(<[Animator; 2]>::default);
+    let mode = use_state(|| Mode::Single);
+    // 哪几个位置交给自动驾驶
+    let bots = use_state(|| [false; 2]);
+    let autopilots = use_mut_ref(<[Autopilot; 2]>::default);
+    // 标题画面闲着时让自动驾驶飞给人看
+    let attract = use_state(|| false);
+    let idle_time = use_mut_ref(|| 0_u32);
+    // 自动驾驶和双人模式的成绩都不算进记录
+    let records = *mode == Mode::Single && !bots[0] && !*attract;
+    // 每帧都在变，不放在state里，免得每帧都重画整个界面
+    let world = use_mut_ref(|| World::new(map_config.w, map_config.h, 1, thread_rng().gen()));
+    let online = use_reducer_eq(Online::default);
+    // 对手的位置，每帧画的时候读
+    let ghosts = use_mut_ref(BTreeMap::<u32, Ghost>::new);
+    let hud = use_state_eq(|| Hud::of(&world.borrow(), &ghosts.borrow()));
+    let online_config = use_state(OnlineConfig::load);
+    let practice = use_state(PracticeConfig::load);
+    // 练习时撞上的障碍物闪一下：障碍物的x，还剩几帧
+    let hit_flashes = use_mut_ref(Vec::<(f64, u32)>::new);
+    let net = use_mut_ref(|| None::<NetClient>);
+    // 联机时数着帧，隔几帧发一次状态
+    let net_frame = use_mut_ref(|| 0_u32);
+    // 每个玩家是否按着，不放在world里免得和每帧的更新互相覆盖
+    let is_flying = use_mut_ref(|| [false; 2]);
 
-    let comming_obstacles_distance = use_state(|| 0_u32);
+    let comming_obstacles_distance = use_state_eq(|| 0_u32);
+    let obstacle_cue = use_mut_ref(ObstacleCue::default);
 
-    let life = use_state(|| N_LIFES);
+    let lives = use_state(|| [N_LIFES; 2]);
     let is_playing = use_state(|| false);
-    let score = use_state(|| 0_u32);
-    let best_score = use_state(get_best_score);
-    let restart_waiting = use_state(|| 0_u32);
+    let score = hud.scores[0];
+    let best_score = use_state(|| get_best("best_score"));
+    // 读屏软件念的，一局只说一次
+    let best_announcement = use_state(String::new);
+    // 单位m
+    let distance = hud.distance;
+    let best_distance = use_state(|| get_best("best_distance"));
+    // 擦边得分，和score分开算
+    let style_score = use_state(|| 0_u32);
+    let best_style_score = use_state(|| get_best("best_style_score"));
+    let style_tracker = use_mut_ref(StyleTracker::default);
+    let particles = use_mut_ref(Particles::default);
+    // 最近几秒的录像，撞了以后慢放
+    let recorder = use_mut_ref(|| Recorder::new(CLIP_FRAMES));
+    let crash_cam = use_mut_ref(CrashCam::default);
+    let crash_replay = use_state(|| None::<Rc<Replay>>);
+    // 倒带用的存档，撞了以后等着退回去的世界
+    let rewind = use_mut_ref(|| Rewind::new(REWIND_STEPS, REWIND_EVERY));
+    let rewound = use_mut_ref(|| None::<World>);
+    let restart_waiting = use_mut_ref(|| 0_u32);
 
     let can_touch = use_memo((), |_| window().unwrap().navigator().max_touch_points() > 0);
     let need_fullscreen_btn = use_state(|| false);
 
     let full_screen_callback = Callback::from(move |_| enter_fullscreen());
 
+    let toasts = use_reducer(Toasts::default);
+    let achievement_tracker = use_mut_ref(AchievementTracker::load);
+    let show_achievements = use_state(|| false);
+    let run_tracker = use_mut_ref(RunTracker::default);
+    let show_stats = use_state(|| false);
+    let show_score_card = use_state(|| false);
+    // 链接里带了种子就一直飞那条赛道
+    let challenge = use_memo((), |_| challenge_seed());
+    let run_seed = use_state(|| 0_u64);
+    let show_leaderboard = use_state(|| false);
+    let settings = use_state(Settings::load);
+    i18n::configure(settings.language, settings.imperial);
+    let show_settings = use_state(|| false);
+    let is_paused = use_state(|| false);
+    let gamepad_state = use_mut_ref(GamepadState::default);
+    let held_fly = use_mut_ref(<[HeldSources; 2]>::default);
+    let show_debug = use_state(|| false);
+
+    let emit_event = {
+        clone_all![toasts, achievement_tracker];
+        Callback::from(move |event: GameEvent| {
+            for a in achievement_tracker.borrow_mut().handle(event) {
+                toasts.dispatch(ToastAction::Push(format!("🏆 {}", tr(a.name))));
+            }
+        })
+    };
+
+    {
+        clone_all![toasts];
+        use_interval(
+            move || {
+                if !toasts.is_empty() {
+                    toasts.dispatch(ToastAction::Expire);
+                }
+            },
+            500,
+        );
+    }
+
+    // 手柄连接/断开提示
+    {
+        clone_all![toasts];
+        use_effect_with((), move |_| {
+            for (event_name, msg) in [
+                ("gamepadconnected", "🎮 Connected"),
+                ("gamepaddisconnected", "🎮 Disconnected"),
+            ] {
+                let toasts = toasts.clone();
+                let listener = Closure::wrap(Box::new(move |e: GamepadEvent| {
+                    let id = e.gamepad().map(|pad| pad.id()).unwrap_or_default();
+                    toasts.dispatch(ToastAction::Push(format!("{}: {id}", tr(msg))));
+                }) as Box<dyn FnMut(GamepadEvent)>);
+                window()
+                    .unwrap()
+                    .add_event_listener_with_callback(event_name, listener.as_ref().unchecked_ref())
+                    .unwrap();
+                listener.forget();
+            }
+        });
+    }
+
+    // 游戏中屏蔽双指缩放之类的浏览器手势
+    let block_gestures = use_mut_ref(|| false);
+    {
+        clone_all![block_gestures];
+        use_effect_with((), move |_| {
+            let listener = Closure::wrap(Box::new(move |e: Event| {
+                if *block_gestures.borrow() && e.cancelable() {
+                    e.prevent_default();
+                }
+            }) as Box<dyn FnMut(Event)>);
+            let options = AddEventListenerOptions::new();
+            options.set_passive(false);
+            let document = window().unwrap().document().unwrap();
+            for event_name in ["touchmove", "gesturestart", "dblclick"] {
+                document
+                    .add_event_listener_with_callback_and_add_event_listener_options(
+                        event_name,
+                        listener.as_ref().unchecked_ref(),
+                        &options,
+                    )
+                    .unwrap();
+            }
+            listener.forget();
+        });
+    }
+    {
+        clone_all![block_gestures];
+        use_effect_with(is_playing.clone(), move |is_playing| {
+            *block_gestures.borrow_mut() = **is_playing;
+        });
+    }
+
     // 初始化canvas和window.onresize
     {
         clone_all![canvas_ref, canvas_ctx, map_config, need_fullscreen_btn];
@@ -181,29 +392,18 @@ fn app() -> Html {
 
             console::log_1(&JsValue::from_str("Set canvas size"));
 
-            canvas.set_width(map_config.w as u32);
-            canvas.set_height(map_config.h as u32);
+            map_config.resize_canvas(&canvas);
             canvas.focus().unwrap();
 
             let ctx = CanvasRenderingContext2d::from(JsValue::from(
-                canvas
-                    .get_context_with_context_options(
-                        "2d",
-                        &JsValue::from_serde(&ContextOtions {
-                            will_read_frequently: true,
-                        })
-                        .unwrap(),
-                    )
-                    .unwrap()
-                    .unwrap(),
+                canvas.get_context("2d").unwrap().unwrap(),
             ));
 
             canvas_ctx.set(Some(ctx));
 
             let update = Closure::wrap(Box::new(move || {
                 let new_map_config = MapConfig::calc();
-                canvas.set_width(new_map_config.w as u32);
-                canvas.set_height(new_map_config.h as u32);
+                new_map_config.resize_canvas(&canvas);
                 map_config.set(new_map_config);
 
                 need_fullscreen_btn.set(
@@ -224,36 +424,97 @@ fn app() -> Html {
         });
     }
     // 新的一局各种初始化
+    let reset_run = {
+        clone_all![
+            world,
+            mode,
+            online,
+            map_config,
+            style_score,
+            style_tracker,
+            particles,
+            recorder,
+            crash_replay,
+            challenge,
+            run_seed,
+            practice,
+            hit_flashes,
+            rewind,
+            rewound,
+            audio_ref,
+            emit_event,
+            run_tracker,
+            is_paused,
+            autopilots,
+            records,
+            best_announcement
+        ];
+        move || {
+            is_paused.set(false);
+            for pilot in autopilots.borrow_mut().iter_mut() {
+                pilot.reset();
+            }
+            // 倒带的话接着撞之前的那一局飞，分数和擦边分都留着
+            if let Some(back) = rewound.borrow_mut().take() {
+                *world.borrow_mut() = back;
+            } else {
+                best_announcement.set(String::new());
+                if records {
+                    emit_event.emit(GameEvent::FlightStarted);
+                }
+                *run_tracker.borrow_mut() = RunTracker::default();
+                let (w, h) = world_size(*mode, &map_config);
+                // 联机时大家用服务器给的同一个种子，练习时用自己选的赛道
+                let seed = match *mode {
+                    Mode::Online => online.seed,
+                    Mode::Practice => practice.seed,
+                    _ => challenge.unwrap_or_else(|| thread_rng().gen()),
+                };
+                run_seed.set(seed);
+                *world.borrow_mut() = if *mode == Mode::Practice {
+                    practice.world(w, h)
+                } else {
+                    World::new(w, h, mode.players(), seed)
+                };
+                style_score.set(0);
+                style_tracker.borrow_mut().reset();
+            }
+            hit_flashes.borrow_mut().clear();
+            rewind.borrow_mut().reset();
+            particles.borrow_mut().reset();
+            recorder.borrow_mut().reset();
+            crash_replay.set(None);
+
+            if let Some(audio) = audio_ref.cast::<HtmlAudioElement>() {
+                audio.set_current_time(0.0);
+                audio.set_volume(0.5);
+                let _ = audio.play().unwrap();
+            }
+        }
+    };
     {
         clone_all![
             is_playing,
-            pos,
-            angle,
-            history,
-            obstacles,
-            score,
-            life,
+            lives,
             audio_ref,
             audio_wall_ref,
             audio_after_ref,
-            restart_waiting
+            restart_waiting,
+            reset_run,
+            recorder,
+            crash_cam,
+            crash_replay,
+            particles
         ];
         use_effect_with(is_playing, move |is_playing| {
             if **is_playing {
-                pos.set(0.);
-                angle.set(0.);
-                history.set(vec![]);
-                obstacles.set(vec![]);
-                score.set(0);
-
-                if let Some(audio) = audio_ref.cast::<HtmlAudioElement>() {
-                    audio.set_current_time(0.0);
-                    audio.set_volume(0.5);
-                    let _ = audio.play().unwrap();
-                }
-            } else if *life < N_LIFES {
+                reset_run();
+            } else if lives.iter().any(|life| *life < N_LIFES) {
                 console::log_1(&JsValue::from_str("Failed"));
-                restart_waiting.set(RESTART_WATING_TIME);
+                *restart_waiting.borrow_mut() = RESTART_WATING_TIME;
+                crash_cam.borrow_mut().reset();
+                particles.borrow_mut().reset();
+                crash_replay.set(Some(Rc::new(recorder.borrow().clip())));
                 if let Some(audio) = audio_ref.cast::<HtmlAudioElement>() {
                     audio.pause().unwrap();
                 }
@@ -265,330 +526,884 @@ fn app() -> Html {
                     audio.set_volume(0.15);
                     let _ = audio.play().unwrap();
                 }
+            } else if let Some(audio) = audio_ref.cast::<HtmlAudioElement>() {
+                // 没撞就停下来的，比如不练了
+                audio.pause().unwrap();
+            }
+        });
+    }
+
+    // 切换模式后重新摆好鸟，回到标题画面
+    {
+        clone_all![
+            world,
+            lives,
+            map_config,
+            restart_waiting,
+            net,
+            online,
+            crash_replay,
+            rewound
+        ];
+        use_effect_with(*mode, move |mode| {
+            let (w, h) = world_size(*mode, &map_config);
+            *world.borrow_mut() = World::new(w, h, mode.players(), thread_rng().gen());
+            lives.set([N_LIFES; 2]);
+            crash_replay.set(None);
+            rewound.borrow_mut().take();
+            *restart_waiting.borrow_mut() = 0;
+            if *mode != Mode::Online && net.borrow_mut().take().is_some() {
+                online.dispatch(OnlineAction::Disconnect);
+            }
+        });
+    }
+
+    // 服务器说开始就开始
+    {
+        clone_all![mode, is_playing, reset_run];
+        use_effect_with(online.round, move |round| {
+            if *round > 0 && *mode == Mode::Online {
+                if *is_playing {
+                    reset_run();
+                } else {
+                    is_playing.set(true);
+                }
             }
         });
     }
 
+    let on_connect = {
+        clone_all![net, online, online_config, ghosts];
+        Callback::from(move |_: ()| {
+            online.dispatch(OnlineAction::Connecting);
+            ghosts.borrow_mut().clear();
+            let dispatcher = online.dispatcher();
+            let on_event = {
+                clone_all![ghosts];
+                Callback::from(move |e: NetEvent| {
+                    match &e {
+                        // 对手的位置不经过Online，记下来等下一帧画
+                        NetEvent::Message(ServerMessage::State { id, state }) => {
+                            ghosts
+                                .borrow_mut()
+                                .entry(*id)
+                                .or_default()
+                                .push(Date::now(), *state);
+                            return;
+                        }
+                        NetEvent::Message(ServerMessage::Start { .. }) | NetEvent::Closed(_) => {
+                            ghosts.borrow_mut().clear()
+                        }
+                        NetEvent::Message(ServerMessage::Left { id }) => {
+                            ghosts.borrow_mut().remove(id);
+                        }
+                        _ => {}
+                    }
+                    dispatcher.dispatch(OnlineAction::Event(e));
+                })
+            };
+            match NetClient::connect(&online_config, on_event.clone()) {
+                Ok(client) => *net.borrow_mut() = Some(client),
+                Err(_) => on_event.emit(NetEvent::Closed(tr("Invalid server address").to_string())),
+            }
+        })
+    };
+
+    let on_disconnect = {
+        clone_all![net, online, ghosts];
+        Callback::from(move |_: ()| {
+            net.borrow_mut().take();
+            ghosts.borrow_mut().clear();
+            online.dispatch(OnlineAction::Disconnect);
+        })
+    };
+
     // 更新best_score
     {
-        clone_all![score, best_score];
+        clone_all![best_score, emit_event, best_announcement, particles, world];
         use_effect_with(score, move |score| {
-            if **score > *best_score {
-                best_score.set(**score);
-                set_best_score(**score);
+            if records && *score > *best_score {
+                emit_event.emit(GameEvent::NewBest);
+                if best_announcement.is_empty() {
+                    best_announcement.set(tr("New best score!").to_string());
+                    let world = world.borrow();
+                    let bird = &world.birds[0];
+                    particles.borrow_mut().best(bird.x, world.bird_y(bird));
+                }
+                best_score.set(*score);
+                set_best("best_score", *score);
+            }
+        });
+    }
+
+    // 快到下一个障碍物时嘀几声，越近音越高
+    {
+        clone_all![settings, obstacle_cue, is_playing];
+        use_effect_with(*comming_obstacles_distance, move |distance| {
+            if *is_playing
+                && settings.obstacle_cues
+                && !settings.muted
+                && (1..=CUE_FROM).contains(distance)
+            {
+                obstacle_cue.borrow_mut().beep(*distance);
+            }
+        });
+    }
+
+    // 更新best_distance
+    {
+        clone_all![best_distance];
+        use_effect_with(distance, move |distance| {
+            if records && *distance > *best_distance {
+                best_distance.set(*distance);
+                set_best("best_distance", *distance);
+            }
+        });
+    }
+
+    // 更新best_style_score
+    {
+        clone_all![style_score, best_style_score];
+        use_effect_with(style_score, move |style_score| {
+            if **style_score > *best_style_score {
+                best_style_score.set(**style_score);
+                set_best("best_style_score", **style_score);
+            }
+        });
+    }
+
+    // 暂停时音乐也停
+    {
+        clone_all![audio_ref, is_playing, is_paused];
+        use_effect_with(is_paused, move |is_paused| {
+            if let Some(audio) = audio_ref.cast::<HtmlAudioElement>() {
+                if **is_paused {
+                    audio.pause().unwrap();
+                } else if *is_playing {
+                    let _ = audio.play().unwrap();
+                }
+            }
+        });
+    }
+
+    // 静音
+    {
+        clone_all![audio_ref, audio_wall_ref, audio_after_ref];
+        use_effect_with(settings.muted, move |muted| {
+            for audio in [audio_ref, audio_wall_ref, audio_after_ref] {
+                if let Some(audio) = audio.cast::<HtmlAudioElement>() {
+                    audio.set_muted(*muted);
+                }
             }
         });
     }
 
     // 载入图片
     let img_onload = {
-        let bird_image = bird_image.clone();
+        clone_all![bird_image, bird_loads];
         Callback::from(move |event: Event| {
             let bird = event.target_dyn_into::<HtmlImageElement>().unwrap();
             bird_image.set(Some(bird));
+            bird_loads.set(*bird_loads + 1);
+        })
+    };
+    let stock_img_onload = {
+        clone_all![stock_bird_image, bird_loads];
+        Callback::from(move |event: Event| {
+            stock_bird_image.set(Some(event.target_dyn_into::<HtmlImageElement>().unwrap()));
+            bird_loads.set(*bird_loads + 1);
         })
     };
+    let bird_src = match &*custom_bird {
+        Some(src) if settings.skin == custom_bird::CUSTOM_SKIN => src.clone(),
+        _ => map_config.bird_sprite().to_string(),
+    };
 
-    let start_fly_core = {
-        clone_all![is_flying, is_playing, restart_waiting];
+    {
+        clone_all![sprite_sheets];
+        use_effect_with(
+            (
+                (*bird_image).clone(),
+                *bird_loads,
+                (*stock_bird_image).clone(),
+                settings.skin.clone(),
+            ),
+            move |(img, _, stock_img, skin)| {
+                // 换图时要等新图载入
+                if let (Some(img), Some(stock_img)) = (
+                    img.as_ref().filter(|img| img.complete()),
+                    stock_img.as_ref().filter(|img| img.complete()),
+                ) {
+                    let [p1, p2] = player_skins(Skin::by_id(skin));
+                    sprite_sheets.set(Some(Rc::new([
+                        SpriteSheet::build(img, p1),
+                        SpriteSheet::build(stock_img, p2),
+                    ])));
+                }
+            },
+        );
+    }
+
+    // 联机时不自己开始，告诉服务器准备好了
+    let get_ready = {
+        clone_all![net, online];
         move || {
-            if *is_flying {
-                return;
+            if online.status == Status::InRoom && !online.is_ready() {
+                if let Some(net) = net.borrow().as_ref() {
+                    net.send(&ClientMessage::Ready);
+                }
             }
-            is_flying.set(true);
-            if *restart_waiting == 0 && !*is_playing {
-                is_playing.set(true);
+        }
+    };
+
+    // 有输入就不算闲着，正在演示的话回到标题画面，这次输入不算数
+    let wake = {
+        clone_all![idle_time, attract, world, mode, map_config];
+        move || {
+            *idle_time.borrow_mut() = 0;
+            if !*attract {
+                return false;
+            }
+            attract.set(false);
+            let (w, h) = world_size(*mode, &map_config);
+            *world.borrow_mut() = World::new(w, h, mode.players(), thread_rng().gen());
+            true
+        }
+    };
+
+    let start_fly_core = {
+        clone_all![is_flying, is_playing, restart_waiting, mode, get_ready];
+        move |player: usize| {
+            is_flying.borrow_mut()[player] = true;
+            if *restart_waiting.borrow() == 0 && !*is_playing {
+                if *mode == Mode::Online {
+                    get_ready();
+                } else {
+                    is_playing.set(true);
+                }
             }
         }
     };
 
     let end_fly_core = {
         clone_all![is_flying];
-        move || {
-            is_flying.set(false);
+        move |player: usize| {
+            is_flying.borrow_mut()[player] = false;
         }
     };
 
+    let on_action = {
+        clone_all![
+            held_fly,
+            start_fly_core,
+            end_fly_core,
+            mode,
+            is_playing,
+            is_paused,
+            restart_waiting,
+            reset_run,
+            get_ready,
+            settings,
+            show_debug,
+            is_flying,
+            rewound
+        ];
+        move |action: Action, source: Source, pressed: bool| match action {
+            Action::Fly | Action::Player1Fly | Action::Player2Fly => {
+                if pressed {
+                    let player = if action == Action::Player2Fly && *mode == Mode::TwoPlayer {
+                        1
+                    } else {
+                        0
+                    };
+                    if held_fly.borrow_mut()[player].press(source) {
+                        // 切换模式下再按一次就停，但开局那一下总是开始
+                        if settings.toggle_fly && *is_playing && is_flying.borrow()[player] {
+                            end_fly_core(player);
+                        } else {
+                            start_fly_core(player);
+                        }
+                    }
+                } else {
+                    // 松开时不知道是谁按下的，都找一遍
+                    for (player, held) in held_fly.borrow_mut().iter_mut().enumerate() {
+                        if held.release(&source) && !settings.toggle_fly {
+                            end_fly_core(player);
+                        }
+                    }
+                }
+            }
+            _ if !pressed => {}
+            // 联机时停下来对手会看到鸟卡住，不让暂停
+            Action::Pause => {
+                if *is_playing && *mode != Mode::Online {
+                    is_paused.set(!*is_paused);
+                }
+            }
+            Action::Restart if *mode == Mode::Online => {
+                if !*is_playing && *restart_waiting.borrow() == 0 {
+                    get_ready();
+                }
+            }
+            Action::Restart => {
+                if *is_playing {
+                    reset_run();
+                } else if *restart_waiting.borrow() == 0 {
+                    // 重新开始就不倒带了
+                    rewound.borrow_mut().take();
+                    is_playing.set(true);
+                }
+            }
+            Action::Mute => {
+                let mut new_settings = (*settings).clone();
+                new_settings.muted = !new_settings.muted;
+                new_settings.save();
+                settings.set(new_settings);
+            }
+            Action::ToggleDebug => show_debug.set(!*show_debug),
+        }
+    };
+
+    let on_key_down = {
+        clone_all![settings, on_action, wake];
+        Callback::from(move |e: KeyboardEvent| {
+            // 没绑定的键也能叫醒
+            if wake() {
+                e.prevent_default();
+                return;
+            }
+            let Some(binding) = Binding::from_key(&e) else {
+                return;
+            };
+            let Some(action) = settings.bindings.action_for(&binding) else {
+                return;
+            };
+            e.prevent_default();
+            if e.repeat() && action != Action::Fly {
+                return;
+            }
+            on_action(action, Source::Key(e.code()), true);
+        })
+    };
+
+    // 松开时不管绑定，按下过就要松开
+    let on_key_up = {
+        clone_all![on_action];
+        Callback::from(move |e: KeyboardEvent| on_action(Action::Fly, Source::Key(e.code()), false))
+    };
+
+    let on_pointer_down = {
+        clone_all![settings, mode, on_action, wake];
+        Callback::from(move |e: PointerEvent| {
+            if wake() {
+                return;
+            }
+            if let Some(action) = settings.bindings.action_for(&Binding::from_pointer(&e)) {
+                // 手指移出canvas也能收到pointerup
+                if let Some(canvas) = e.target_dyn_into::<Element>() {
+                    let _ = canvas.set_pointer_capture(e.pointer_id());
+                }
+                // 双人模式按屏幕左右两半分给两个玩家
+                let action = match action {
+                    Action::Fly if *mode == Mode::TwoPlayer => {
+                        let half = window().unwrap().inner_width().unwrap().as_f64().unwrap() / 2.;
+                        if (e.client_x() as f64) < half {
+                            Action::Player1Fly
+                        } else {
+                            Action::Player2Fly
+                        }
+                    }
+                    action => action,
+                };
+                on_action(action, Source::Pointer(e.pointer_id()), true);
+            }
+        })
+    };
+
+    // pointerup、pointercancel和lostpointercapture都当成松开
+    let on_pointer_up = {
+        clone_all![on_action];
+        Callback::from(move |e: PointerEvent| {
+            on_action(Action::Fly, Source::Pointer(e.pointer_id()), false)
+        })
+    };
+
+    let on_blur = {
+        clone_all![held_fly, end_fly_core];
+        Callback::from(move |_| {
+            for (player, held) in held_fly.borrow_mut().iter_mut().enumerate() {
+                if held.release_all() {
+                    end_fly_core(player);
+                }
+            }
+        })
+    };
+
     // 核心部分，每过一帧计算运动
     {
+        let frame_interval = if *mode == Mode::Practice {
+            practice.frame_interval()
+        } else {
+            settings.speed.frame_interval()
+        };
         clone_all![
             canvas_ctx,
-            angle,
-            bird_image,
+            sprite_sheets,
+            animators,
+            world,
+            hud,
+            ghosts,
+            mode,
             is_flying,
-            pos,
-            history,
             is_playing,
-            life,
-            score,
+            lives,
+            style_score,
+            style_tracker,
+            particles,
+            recorder,
+            crash_cam,
+            crash_replay,
             comming_obstacles_distance,
             restart_waiting,
-            map_config
+            map_config,
+            emit_event,
+            run_tracker,
+            settings,
+            is_paused,
+            gamepad_state,
+            on_action,
+            show_debug,
+            audio_wall_ref,
+            online,
+            net,
+            net_frame,
+            bots,
+            autopilots,
+            records,
+            practice,
+            hit_flashes,
+            rewind,
+            rewound,
+            attract,
+            idle_time,
+            show_achievements,
+            show_stats,
+            show_leaderboard,
+            show_settings,
+            wake
         ];
         use_interval(
             move || {
-                let MapConfig { w, h } = *map_config;
-                if let Some(ctx) = canvas_ctx.as_ref() {
-                    if let Some(bird) = bird_image.as_ref() {
-                        if !*is_playing && *life < N_LIFES {
-                            if *restart_waiting > 0 {
-                                restart_waiting
-                                    .set(*restart_waiting - INTERV.min(*restart_waiting));
-                            }
-                            return;
+                // 手柄没有事件，只能每帧去读
+                let pad = gamepad::poll();
+                let last_pad = gamepad_state.replace(pad);
+                if ((pad.fly && !last_pad.fly) || (pad.start && !last_pad.start)) && wake() {
+                    return;
+                }
+                if pad.fly != last_pad.fly {
+                    on_action(Action::Fly, Source::Gamepad, pad.fly);
+                }
+                if pad.start && !last_pad.start {
+                    on_action(Action::Pause, Source::Gamepad, true);
+                }
+                if *is_paused {
+                    return;
+                }
+
+                let (w, h) = world_size(*mode, &map_config);
+                let (Some(ctx), Some(sheets)) = (canvas_ctx.as_ref(), sprite_sheets.as_deref())
+                else {
+                    return;
+                };
+                let trail_len = if settings.reduced_motion {
+                    REDUCED_HISTORY_LEN
+                } else {
+                    HISTORY_LEN
+                };
+                if !*is_playing && lives.iter().any(|life| *life < N_LIFES) {
+                    {
+                        let mut waiting = restart_waiting.borrow_mut();
+                        *waiting -= frame_interval.min(*waiting);
+                    }
+                    // 不停在最后一帧，慢放撞上前的几秒
+                    let Some(shot) = crash_replay.as_ref().and_then(|replay| {
+                        crash_cam.borrow_mut().next(replay, settings.reduced_motion)
+                    }) else {
+                        return;
+                    };
+                    let scale = map_config.h / shot.world.h * map_config.pixel_ratio;
+                    ctx.set_transform(scale, 0., 0., scale, 0., 0.).unwrap();
+                    let palette = Palette::get(settings.high_contrast);
+                    let mut renderer = CanvasRenderer { ctx, sheets };
+                    draw_background(&mut renderer, &shot.world, palette);
+                    ctx.transform(shot.zoom, 0., 0., shot.zoom, shot.offset.0, shot.offset.1)
+                        .unwrap();
+                    let poses = render::poses(&shot.world, &mut animators.borrow_mut());
+                    draw_world(
+                        &mut renderer,
+                        &shot.world,
+                        &poses,
+                        player_skins(Skin::by_id(&settings.skin)).map(|skin| skin.tint),
+                        palette,
+                        trail_len,
+                    );
+                    let mut particles = particles.borrow_mut();
+                    if let Some((x, y)) = shot.impact {
+                        particles.crash(x, y);
+                    }
+                    particles.step();
+                    particles.draw(ctx, shot.world.to_screen_x(0.));
+                    return;
+                }
+
+                let on_title = !*is_playing
+                    && *mode != Mode::Online
+                    && !*show_achievements
+                    && !*show_stats
+                    && !*show_leaderboard
+                    && !*show_settings;
+                if !on_title {
+                    *idle_time.borrow_mut() = 0;
+                    if *attract {
+                        attract.set(false);
+                        *world.borrow_mut() = World::new(w, h, mode.players(), thread_rng().gen());
+                        return;
+                    }
+                } else if !*attract {
+                    let mut idle = idle_time.borrow_mut();
+                    *idle += frame_interval;
+                    if *idle >= ATTRACT_AFTER {
+                        *idle = 0;
+                        for pilot in autopilots.borrow_mut().iter_mut() {
+                            pilot.reset();
                         }
+                        *world.borrow_mut() = World::new(w, h, mode.players(), thread_rng().gen());
+                        attract.set(true);
+                        return;
+                    }
+                }
 
-                        ctx.set_fill_style(&JsValue::from_str(&format!(
-                            "rgb({BG_COLOR}, {BG_COLOR}, {BG_COLOR})"
-                        )));
-                        ctx.fill_rect(0., 0., w, h);
-                        ctx.save();
-
-                        // 画轨迹 && 画鸟
-                        let (ox, oy) = (w / 3., h / 2.);
-                        let pos_y = oy + *pos;
-                        ctx.translate(ox, pos_y).unwrap();
-
-                        ctx.set_stroke_style(&JsValue::from_str("white"));
-                        ctx.set_line_width(3.);
-                        ctx.begin_path();
-                        let mut cnt = 0;
-                        for (x, y) in history.iter() {
-                            ctx.line_to(*x, *y);
-                            cnt += 1;
-                            if cnt % HISTORY_COLOR_CHANGE == 0 {
-                                ctx.stroke();
-                                let new_color = 255 - cnt / HISTORY_COLOR_CHANGE;
-                                ctx.set_stroke_style(&JsValue::from_str(&format!(
-                                    "rgb({new_color}, {new_color}, {new_color})"
-                                )));
-                                ctx.begin_path();
-                                ctx.move_to(*x, *y);
-                            }
+                // 窗口拖到另一个显示器上时不一定有resize
+                if window().unwrap().device_pixel_ratio() != map_config.dpr {
+                    let new_map_config = MapConfig::calc();
+                    if let Some(canvas) = ctx.canvas() {
+                        new_map_config.resize_canvas(&canvas);
+                    }
+                    map_config.set(new_map_config);
+                    return;
+                }
+
+                // 就地更新，不每帧复制一份
+                let mut world = world.borrow_mut();
+                world.resize(w, h);
+                // 下面都按世界的大小画
+                let scale = map_config.h / h * map_config.pixel_ratio;
+                ctx.set_transform(scale, 0., 0., scale, 0., 0.).unwrap();
+                // 世界坐标加上dx就是屏幕坐标
+                let dx = world.to_screen_x(0.);
+
+                let palette = Palette::get(settings.high_contrast);
+                let mut renderer = CanvasRenderer { ctx, sheets };
+                draw_background(&mut renderer, &world, palette);
+                style_tracker.borrow().draw(ctx, dx);
+
+                // 对手的鸟画成半透明的
+                let now = Date::now();
+                for (id, ghost) in ghosts.borrow().iter() {
+                    let Some(state) = ghost.sample(now) else {
+                        continue;
+                    };
+                    let (x, y) = (state.x + dx, h / 2. + state.pos);
+                    let alpha = if online.crashed.contains(id) {
+                        0.2
+                    } else {
+                        0.4
+                    };
+                    ctx.save();
+                    ctx.set_global_alpha(alpha);
+                    ctx.set_fill_style_str("black");
+                    ctx.set_font("28px sans-serif");
+                    ctx.set_text_align("center");
+                    ctx.fill_text(online.name_of(*id), x, y - BIRD_SIZE / 2. - 10.)
+                        .unwrap();
+                    ctx.restore();
+                    renderer.draw_bird(1, (Anim::Glide, 0), x, y, state.angle, alpha);
+                }
+
+                // 1号玩家的轨迹跟着皮肤的颜色
+                let tints = player_skins(Skin::by_id(&settings.skin)).map(|skin| skin.tint);
+
+                // 自动驾驶打算怎么飞，画成一串点
+                for ((pilot, tint), bot) in autopilots.borrow().iter().zip(tints).zip(*bots) {
+                    if !bot {
+                        continue;
+                    }
+                    let color = Color::rgb(trail_rgb(tint, PATH_SHADE));
+                    for (x, y) in pilot.path().iter().step_by(PATH_DOT_EVERY) {
+                        renderer.fill_rect(x + dx - 3., y - 3., 6., 6., color);
+                    }
+                }
+
+                let poses = render::poses(&world, &mut animators.borrow_mut());
+                draw_world(&mut renderer, &world, &poses, tints, palette, trail_len);
+                particles.borrow().draw(ctx, dx);
+
+                // 越到后面越淡
+                hit_flashes.borrow_mut().retain_mut(|(x, left)| {
+                    let color =
+                        Color::rgb(HIT_FLASH).alpha(*left as f64 / FLASH_STEPS as f64 * 0.6);
+                    renderer.fill_rect(*x + dx, 0., OB_WIDTH, h, color);
+                    *left -= 1;
+                    *left > 0
+                });
+
+                // 给预警
+                comming_obstacles_distance.set(
+                    world
+                        .next_obstacle()
+                        .map_or(0, |ob| ((ob.x + dx - w) / 100.0) as u32),
+                );
+                draw_warning(&mut renderer, &world, palette);
+                hud.set(Hud::of(&world, &ghosts.borrow()));
+
+                if !*is_playing && !*attract {
+                    return;
+                }
+
+                let single = records;
+                let mut flying = *is_flying.borrow();
+                // 自动驾驶不管玩家按没按
+                {
+                    let mut pilots = autopilots.borrow_mut();
+                    for (i, pilot) in pilots.iter_mut().enumerate() {
+                        if (bots[i] || *attract) && world.birds.get(i).is_some_and(|b| b.alive) {
+                            flying[i] = pilot.decide(&world, i);
                         }
-                        ctx.stroke();
-
-                        ctx.rotate(*angle).unwrap();
-                        ctx.draw_image_with_html_image_element_and_dw_and_dh(
-                            bird,
-                            -BIRD_SIZE / 2.,
-                            -BIRD_SIZE / 2.,
-                            BIRD_SIZE,
-                            BIRD_SIZE,
-                        )
-                        .expect("draw bird failed");
-                        ctx.restore();
-
-                        // 画障碍物
-                        ctx.set_fill_style(&JsValue::from_str(&format!(
-                            "rgb({OB_COLOR}, {OB_COLOR}, {OB_COLOR})"
-                        )));
-                        for Obstacle { x, y1, y2 } in obstacles.iter() {
-                            ctx.fill_rect(*x, 0., OB_WIDTH, *y1);
-                            ctx.fill_rect(*x, *y2, OB_WIDTH, h - *y2);
+                    }
+                }
+                for (bird, flying) in world.birds.iter_mut().zip(flying) {
+                    bird.flying = flying;
+                }
+                // 多个手柄合在一起看，只给1号玩家
+                world.birds[0].climb_rate = match pad.trigger {
+                    Some(t) if settings.analog_triggers && !bots[0] && !*attract => t,
+                    _ => 1.0,
+                };
+
+                let (old_x, pos_y) = {
+                    let bird = &world.birds[0];
+                    if single && bird.alive {
+                        // 快到障碍物时还不算，鸟在两根管子中间才记
+                        if let Some(ob) = world
+                            .current_obstacle(bird)
+                            .filter(|ob| (ob.x..=ob.x + OB_WIDTH).contains(&bird.x))
+                        {
+                            style_tracker
+                                .borrow_mut()
+                                .observe(world.bird_y(bird), ob.y1, ob.y2);
                         }
+                    }
+                    (bird.x, world.bird_y(bird))
+                };
 
-                        // 给预警
-                        if let Some(ob) = obstacles.iter().find(|ob| ob.x + OB_WIDTH > w) {
-                            if ob.x > w {
-                                comming_obstacles_distance.set(((ob.x - w) / 100.0) as u32);
-                                ctx.set_fill_style(&JsValue::from_str(&format!(
-                                    "rgb({NEXT_OB_COLOR}, {NEXT_OB_COLOR}, {NEXT_OB_COLOR})"
-                                )));
-                                ctx.fill_rect(w - NEXT_OB_WIDTH, 0.0, NEXT_OB_WIDTH, ob.y1);
-                                ctx.fill_rect(w - NEXT_OB_WIDTH, ob.y2, NEXT_OB_WIDTH, h - ob.y2);
-                            }
-                        } else {
-                            comming_obstacles_distance.set(0);
+                let events = world.step();
+
+                {
+                    let mut particles = particles.borrow_mut();
+                    particles.set_low_quality(settings.low_quality);
+                    for event in events.iter() {
+                        if let SimEvent::Crashed { bird, .. } = event {
+                            let bird = &world.birds[*bird];
+                            particles.crash(bird.x, world.bird_y(bird));
+                        }
+                    }
+                    for bird in world.birds.iter().filter(|b| b.alive) {
+                        let y = world.bird_y(bird);
+                        particles.skim(bird.x, y, h);
+                        if !settings.reduced_motion {
+                            particles.speed_lines(bird.x, y, world.speed(bird));
                         }
+                    }
+                    particles.step();
+                }
 
-                        if !*is_playing {
-                            return;
+                // 演示不算命也不放声音，撞了就换一条赛道接着飞
+                if *attract {
+                    if world.is_over() {
+                        for pilot in autopilots.borrow_mut().iter_mut() {
+                            pilot.reset();
                         }
+                        *world = World::new(w, h, mode.players(), thread_rng().gen());
+                    }
+                    return;
+                }
+                recorder.borrow_mut().record(&world);
+                if *mode == Mode::Single && settings.rules == Rules::Rewind {
+                    rewind.borrow_mut().record(&world);
+                }
 
-                        // 检查是否撞击
-                        // 初筛
-                        let curr_obstacles = obstacles
-                            .iter()
-                            .find(|ob| ob.x - CHECK_SIZE < ox && ox < ob.x + OB_WIDTH + CHECK_SIZE);
-                        let (min_pos, max_pos) = if let Some(ob) = curr_obstacles {
-                            (ob.y1, ob.y2)
-                        } else {
-                            (0.0, h)
-                        };
-
-                        let down_collision = pos_y + CHECK_SIZE > max_pos
-                            && (
-                                // 细筛
-                                curr_obstacles
-                                    .map(|ob| {
-                                        // 读出来的rgb会有变化，先不用处理，
-                                        // colorSpace: "display-p3" 浏览器还没都支持
-                                        // https://stackoverflow.com/questions/70480792/getimagedata-does-not-return-correct-rgb-values-display-p3-image
-                                        let start_y = (pos_y - CHECK_SIZE).max(ob.y2);
-                                        ctx.get_image_data(
-                                            ob.x,
-                                            start_y,
-                                            -1.0,
-                                            (h - start_y).min(2.0 * CHECK_SIZE),
-                                        )
-                                        .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                        .unwrap_or(false)
-                                            || ctx
-                                                .get_image_data(
-                                                    ob.x + OB_WIDTH + 1.0,
-                                                    start_y,
-                                                    1.0,
-                                                    (h - start_y).min(2.0 * CHECK_SIZE),
-                                                )
-                                                .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                                .unwrap_or(false)
-                                            || ctx
-                                                .get_image_data(ob.x, ob.y2, OB_WIDTH, -1.)
-                                                .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                                .unwrap_or(false)
-                                    })
-                                    .unwrap_or(false)
-                                    || ctx
-                                        .get_image_data(ox - CHECK_SIZE, h, 2.0 * CHECK_SIZE, -1.0)
-                                        .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                        .unwrap_or(false)
-                            );
-                        let up_collision = pos_y - CHECK_SIZE < min_pos
-                            && (curr_obstacles
-                                .map(|ob| {
-                                    let start_y = (pos_y - CHECK_SIZE).max(0.0);
-                                    ctx.get_image_data(
-                                        ob.x,
-                                        start_y,
-                                        -1.0,
-                                        (ob.y1 - start_y).min(2.0 * CHECK_SIZE),
-                                    )
-                                    .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                    .unwrap_or(false)
-                                        || ctx
-                                            .get_image_data(
-                                                ob.x + OB_WIDTH + 1.0,
-                                                start_y,
-                                                1.0,
-                                                (ob.y1 - start_y).min(2.0 * CHECK_SIZE),
-                                            )
-                                            .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                            .unwrap_or(false)
-                                        || ctx
-                                            .get_image_data(ob.x, ob.y1, OB_WIDTH, 1.0)
-                                            .unwrap()
-                                            .data()
-                                            .0
-                                            .iter()
-                                            .any(|v| *v < 50)
-                                })
-                                .unwrap_or(false)
-                                || ctx
-                                    .get_image_data(ox - CHECK_SIZE, 0.0, 2.0 * CHECK_SIZE, 1.0)
-                                    .map(|idata| idata.data().0.iter().any(|v| *v < 50))
-                                    .unwrap_or(false));
-
-                        if down_collision || up_collision {
-                            is_playing.set(false);
-                            life.set(*life - 1);
-                            /*
-                            if let Some(ob) = curr_obstacles {
-                                ctx.set_fill_style(&JsValue::from_str("red"));
-                                if down_collision {
-                                    let start_y = (pos_y - CHECK_SIZE).max(ob.y2);
-                                    ctx.fill_rect(
-                                        ob.x,
-                                        start_y,
-                                        -1.0,
-                                        (h - start_y).min(2.0 * CHECK_SIZE),
-                                    );
-                                    ctx.fill_rect(ob.x, ob.y2, OB_WIDTH, -1.0);
-                                }
+                let xl = world.birds[0].x - old_x;
+                if single && world.birds[0].alive {
+                    run_tracker.borrow_mut().step(xl);
+                    emit_event.emit(if flying[0] {
+                        GameEvent::Climbed
+                    } else {
+                        GameEvent::Glided(xl / 100.0)
+                    });
+                }
+                style_tracker.borrow_mut().step(settings.reduced_motion);
 
-                                if up_collision {
-                                    let start_y = (pos_y - CHECK_SIZE).max(0.0);
-                                    ctx.fill_rect(
-                                        ob.x,
-                                        start_y,
-                                        -5.0,
-                                        (ob.y1 - start_y).min(2.0 * CHECK_SIZE),
-                                    );
-                                    ctx.fill_rect(
-                                        ob.x + OB_WIDTH + 1.0,
-                                        start_y,
-                                        5.0,
-                                        (ob.y1 - start_y).min(2.0 * CHECK_SIZE),
-                                    );
-                                    ctx.fill_rect(ob.x, ob.y1, OB_WIDTH, 1.0);
+                let mut new_lives = *lives;
+                for event in events {
+                    match event {
+                        SimEvent::Crashed { bird, cause } => {
+                            new_lives[bird] -= 1;
+                            match *mode {
+                                Mode::Single => {
+                                    emit_event.emit(GameEvent::Crashed);
+                                    // 还有命就能倒带，这一局还没完
+                                    let back = (settings.rules == Rules::Rewind
+                                        && new_lives[0] > 0)
+                                        .then(|| rewind.borrow_mut().rewind(&world))
+                                        .flatten();
+                                    if back.is_none() {
+                                        add_run(run_tracker.borrow().finish(
+                                            world.birds[0].score,
+                                            *style_score,
+                                            cause,
+                                        ));
+                                    }
+                                    *rewound.borrow_mut() = back;
                                 }
+                                Mode::Online => {
+                                    if let Some(net) = net.borrow().as_ref() {
+                                        net.send(&ClientMessage::Crashed {
+                                            score: world.birds[0].score,
+                                        });
+                                    }
+                                }
+                                Mode::TwoPlayer if !world.is_over() => {
+                                    if let Some(audio) = audio_wall_ref.cast::<HtmlAudioElement>() {
+                                        audio.set_volume(1.0);
+                                        let _ = audio.play().unwrap();
+                                    }
+                                }
+                                Mode::TwoPlayer | Mode::Practice => {}
+                            }
+                        }
+                        SimEvent::Hit { bird, .. } => {
+                            if let Some(ob) = world.current_obstacle(&world.birds[bird]) {
+                                let mut flashes = hit_flashes.borrow_mut();
+                                flashes.retain(|(x, _)| *x != ob.x);
+                                flashes.push((ob.x, FLASH_STEPS));
                             }
-                            */
-                            return;
                         }
+                        SimEvent::Passed {
+                            bird: 0,
+                            obstacle: ob,
+                        } if single => {
+                            emit_event.emit(GameEvent::PassedObstacle);
+                            let bonus = style_tracker.borrow_mut().pass(ob.x, ob.y1, ob.y2, pos_y);
+                            if bonus > 0 {
+                                style_score.set(*style_score + bonus);
+                            }
+                        }
+                        SimEvent::Looped { bird: 0 } if single => {
+                            emit_event.emit(GameEvent::Looped)
+                        }
+                        _ => {}
+                    }
+                }
+                if new_lives != *lives {
+                    lives.set(new_lives);
+                }
+                if world.is_over() {
+                    is_playing.set(false);
+                }
+                // 练完一段回到开头再来
+                if *mode == Mode::Practice && practice.section_done(world.birds[0].score) {
+                    *world = practice.world(w, h);
+                    recorder.borrow_mut().reset();
+                    particles.borrow_mut().reset();
+                    hit_flashes.borrow_mut().clear();
+                }
 
-                        // 计算运动
-                        let v = (V_MIN_2 + (pos_y / h) * (V_MAX_2 - V_MIN_2)).sqrt();
+                if *mode == Mode::Online && world.birds[0].alive {
+                    let mut frame = net_frame.borrow_mut();
+                    *frame += 1;
+                    if *frame % SEND_EVERY == 0 {
+                        if let Some(net) = net.borrow().as_ref() {
+                            let bird = &world.birds[0];
+                            net.send(&ClientMessage::State {
+                                state: BirdState {
+                                    x: bird.x,
+                                    pos: bird.pos,
+                                    angle: bird.angle,
+                                    score: bird.score,
+                                },
+                            });
+                        }
+                    }
+                }
 
-                        //console::log_1(&JsValue::from_str(&format!("{}", v)));
-                        let (sin, cos) = angle.sin_cos();
-                        let (xl, yl) = (v * cos, v * sin);
-                        pos.set(*pos + yl);
-                        angle.set(if *is_flying {
-                            *angle + ROTATE_UP
-                        } else {
-                            (yl + ROTATE_DOWN_D).atan2(xl)
-                            //*angle - ROTATE_UP
-                        });
-                        history.set(
-                            iter::once((0., 0.))
-                                .chain(history.iter().map(|(x, y)| (x - xl, y - yl)))
-                                .take(HISTORY_LEN)
-                                .collect::<Vec<(f64, f64)>>(),
-                        );
-
-                        if let Some(ob) = curr_obstacles {
-                            if ox < ob.x + OB_WIDTH && ox + xl > ob.x + OB_WIDTH {
-                                score.set(*score + 1);
-                            }
+                // 调试信息画在最上面，下一帧会被背景盖掉
+                if *show_debug {
+                    let dx = world.to_screen_x(0.);
+                    ctx.set_fill_style_str("red");
+                    ctx.set_stroke_style_str("red");
+                    ctx.set_line_width(2.);
+                    for bird in world.birds.iter() {
+                        for (px, py) in bird.hitbox(world.bird_y(bird)) {
+                            ctx.fill_rect(px + dx - 2., py - 2., 4., 4.);
                         }
-                        let mut new_obstacles: Vec<Obstacle> = obstacles
-                            .iter()
-                            .map(|ob| Obstacle {
-                                x: ob.x - xl,
-                                y1: ob.y1,
-                                y2: ob.y2,
-                            })
-                            .filter(|ob| ob.x > -w)
-                            .collect();
-
-                        if obstacles.last().map(|ob| ob.x).unwrap_or_default() < w {
-                            new_obstacles.push(Obstacle::random_gen(
-                                obstacles.last(),
-                                w,
-                                h,
-                                *score,
-                            ));
+                        if let Some(ob) = world.current_obstacle(bird) {
+                            ctx.stroke_rect(ob.x + dx, ob.y1, OB_WIDTH, ob.y2 - ob.y1);
                         }
-                        obstacles.set(new_obstacles);
                     }
+                    let bird = &world.birds[0];
+                    ctx.set_font("24px monospace");
+                    ctx.fill_text(
+                        &format!(
+                            "v={:.2} angle={:.2} pos={:.0}",
+                            world.speed(bird),
+                            bird.angle,
+                            bird.pos
+                        ),
+                        10.,
+                        h - 10.,
+                    )
+                    .unwrap();
                 }
+
+                hud.set(Hud::of(&world, &ghosts.borrow()));
             },
-            INTERV,
+            frame_interval,
         );
     };
 
+    // 按键提示里用第一个绑定的键
+    let key_label = |action: Action| {
+        settings
+            .bindings
+            .get(action)
+            .iter()
+            .find(|b| matches!(b, Binding::Key(_)))
+            .map(|key| key.label())
+    };
+
     html! {
         <>
             <canvas
                 tabindex="0"
                 ref={canvas_ref}
-                //onmousedown={make_cb!(start_fly_core)}
-                //onmouseup={make_cb!(end_fly_core)}
-                onkeydown={make_cb!(start_fly_core)}
-                onkeyup={make_cb!(end_fly_core)}
-                onpointerdown={make_cb!(start_fly_core)}
-                onpointerup={make_cb!(end_fly_core)}
+                onkeydown={on_key_down}
+                onkeyup={on_key_up}
+                onpointerdown={on_pointer_down}
+                onpointerup={on_pointer_up.clone()}
+                onpointercancel={on_pointer_up.clone()}
+                onlostpointercapture={on_pointer_up}
+                onblur={on_blur}
+                oncontextmenu={Callback::from(|e: MouseEvent| e.prevent_default())}
             />
             <audio loop={true} ref={audio_ref} preload={"auto"}>
                 <source src="static/fantasy_world.mp3" type="audio/mpeg" />
@@ -600,32 +1415,213 @@ fn app() -> Html {
                 <source src="static/string_end.mp3" type="audio/mpeg" />
             </audio>
             <div class="no-select">
-                <img id="birdImage" src="static/bird.webp" onload={img_onload} />
-                <span id="lifeCnt"> {*life} </span>
-                <span class="score"> {format!("{:0>4}", *score)}</span>
-                <span class="score best_score"> {format!("{:0>4}", *best_score)}</span>
+                <img
+                    id="birdImage"
+                    src={bird_src}
+                    style={format!("filter: {}", Skin::by_id(&settings.skin).filter)}
+                    onload={img_onload}
+                />
+                <img
+                    hidden=true
+                    src={map_config.bird_sprite()}
+                    onload={stock_img_onload}
+                />
+                if *mode != Mode::TwoPlayer {
+                    <span id="lifeCnt"> {lives[0]} </span>
+                    <span class="score"> {format!("{:0>4}", score)}</span>
+                    <span class="score best_score"> {format!("{:0>4}", *best_score)}</span>
+                    <div class="odometer">
+                        <span class="distance"> {format_distance(distance as f64)}</span>
+                        <span class="distance best_distance"> {format_distance(*best_distance as f64)}</span>
+                    </div>
+                    <div class="odometer">
+                        <span class="distance"> {format!("✦ {}", *style_score)}</span>
+                        <span class="distance best_distance"> {format!("✦ {}", *best_style_score)}</span>
+                    </div>
+                } else {
+                    <div class="scoreboards">
+                        { for hud.scores.iter().zip(hud.alive).zip(PLAYERS.iter()).zip(lives.iter()).enumerate().map(|(i, (((score, alive), player), life))| html! {
+                            <div class={classes!("scoreboard", player.class, (!alive).then_some("crashed"))}>
+                                <span class="player-name">{ player.name }</span>
+                                if bots[i] { <span class="player-name">{ "🤖" }</span> }
+                                <span class="player-lives">{ format!("♥ {life}") }</span>
+                                <span class="player-score">{ format!("{:0>4}", score) }</span>
+                            </div>
+                        }) }
+                    </div>
+                }
+                if *mode == Mode::Online && !hud.opponents.is_empty() {
+                    <div class="scoreboards opponents">
+                        { for hud.opponents.iter().map(|(id, score)| html! {
+                            <div class={classes!("scoreboard", "p2", online.crashed.contains(id).then_some("crashed"))}>
+                                <span class="player-name">{ online.name_of(*id) }</span>
+                                <span class="player-score">{ format!("{:0>4}", score) }</span>
+                            </div>
+                        }) }
+                    </div>
+                }
+            </div>
+            // 给读屏软件的，分数和命一变就念出来
+            <div class="sr-only" role="status" aria-live="polite" aria-atomic="true">
+                {
+                    if *mode == Mode::TwoPlayer {
+                        hud.scores.iter().zip(PLAYERS.iter()).zip(lives.iter())
+                            .map(|((score, player), life)| trf(
+                                "{player} score {score}, {lives} lives.",
+                                &[("player", &player.name), ("score", score), ("lives", life)],
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    } else {
+                        trf("Score {score}, {lives} lives.", &[("score", &score), ("lives", &lives[0])])
+                    }
+                }
             </div>
+            <div class="sr-only" aria-live="assertive">{ &*best_announcement }</div>
             if !*is_playing {
                 <div id="hint" class="no-select">
                     <p>
                         {
-                            if * can_touch
+                            if *mode == Mode::TwoPlayer
                             {
-                                "Tap to fly"
+                                let p1 = match key_label(Action::Player1Fly) {
+                                    Some(key) => trf("{key} or left half", &[("key", &key)]),
+                                    None => tr("left half").to_string(),
+                                };
+                                let p2 = match key_label(Action::Player2Fly) {
+                                    Some(key) => trf("{key} or right half", &[("key", &key)]),
+                                    None => tr("right half").to_string(),
+                                };
+                                trf("P1: {p1} · P2: {p2}", &[("p1", &p1), ("p2", &p2)])
+                            }
+                            else if *mode == Mode::Online
+                            {
+                                match online.status {
+                                    Status::InRoom if online.is_ready() => tr("Waiting for other players…").to_string(),
+                                    Status::InRoom => tr("Fly when you are ready").to_string(),
+                                    _ => tr("Join a room to race").to_string(),
+                                }
+                            }
+                            else if rewound.borrow().is_some()
+                            {
+                                match key_label(Action::Restart) {
+                                    Some(key) => trf("Fly to go back 3 seconds, or press {key} to start over", &[("key", &key)]),
+                                    None => tr("Fly to go back 3 seconds").to_string(),
+                                }
+                            }
+                            else if * can_touch
+                            {
+                                tr("Tap to fly").to_string()
                             }
                             else
                             {
-                                "Click or press any key to fly"
+                                match key_label(Action::Fly) {
+                                    Some(key) => trf("Click or press {key} to fly", &[("key", &key)]),
+                                    None => tr("Click to fly").to_string(),
+                                }
                             }
                         }
                     </p>
                 </div>
+                if *mode == Mode::Online {
+                    <OnlinePanel
+                        online={(*online).clone()}
+                        config={(*online_config).clone()}
+                        on_config={
+                            let online_config = online_config.clone();
+                            Callback::from(move |config: OnlineConfig| {
+                                config.save();
+                                online_config.set(config);
+                            })
+                        }
+                        {on_connect}
+                        {on_disconnect}
+                    />
+                }
+                if *mode == Mode::Practice {
+                    <PracticePanel
+                        config={(*practice).clone()}
+                        on_change={
+                            let practice = practice.clone();
+                            Callback::from(move |config: PracticeConfig| {
+                                config.save();
+                                practice.set(config);
+                            })
+                        }
+                    />
+                }
+                <div id="menu">
+                    <button onclick={
+                        let mode = mode.clone();
+                        Callback::from(move |_| mode.set(mode.next()))
+                    }>
+                        { mode.icon() }
+                    </button>
+                    if let Some(replay) = (*crash_replay).clone() {
+                        <button title={tr("Save replay")} onclick={Callback::from(move |_| {
+                            download_text(
+                                "flying-bird-replay.json",
+                                "application/json",
+                                &serde_json::to_string(&*replay).unwrap(),
+                            );
+                        })}>
+                            { "🎞" }
+                        </button>
+                    }
+                    if records && lives[0] < N_LIFES {
+                        <button title={tr("Score card")} onclick={
+                            let show_score_card = show_score_card.clone();
+                            Callback::from(move |_| show_score_card.set(true))
+                        }>
+                            { "🖼" }
+                        </button>
+                    }
+                    { for (0..mode.players()).map(|i| {
+                        let bots = bots.clone();
+                        html! {
+                            <button
+                                class={classes!(bots[i].then_some("active"))}
+                                onclick={Callback::from(move |_| {
+                                    let mut new_bots = *bots;
+                                    new_bots[i] = !new_bots[i];
+                                    bots.set(new_bots);
+                                })}
+                            >
+                                { if mode.players() > 1 { format!("🤖{}", i + 1) } else { "🤖".to_string() } }
+                            </button>
+                        }
+                    }) }
+                    <button onclick={
+                        let show_achievements = show_achievements.clone();
+                        Callback::from(move |_| show_achievements.set(true))
+                    }>
+                        { "🏆" }
+                    </button>
+                    <button onclick={
+                        let show_stats = show_stats.clone();
+                        Callback::from(move |_| show_stats.set(true))
+                    }>
+                        { "📊" }
+                    </button>
+                    <button onclick={
+                        let show_leaderboard = show_leaderboard.clone();
+                        Callback::from(move |_| show_leaderboard.set(true))
+                    }>
+                        { "🥇" }
+                    </button>
+                    <button onclick={
+                        let show_settings = show_settings.clone();
+                        Callback::from(move |_| show_settings.set(true))
+                    }>
+                        { "⚙" }
+                    </button>
+                </div>
                 if *need_fullscreen_btn {
                     <button class="fullscreen-btn" onclick={full_screen_callback}>
                         <img src="static/enlarge.svg" />
                     </button>
                 }
-                if *life == N_LIFES {
+                if *lives == [N_LIFES; 2] {
                     <div id="badges">
                         <a href="https://notbyai.fyi/">
                             <img
@@ -654,8 +1650,93 @@ fn app() -> Html {
                     </div>
                 }
             }
+            if *show_achievements {
+                <AchievementsScreen
+                    progress={achievement_tracker.borrow().progress().clone()}
+                    on_close={
+                        let show_achievements = show_achievements.clone();
+                        Callback::from(move |_| show_achievements.set(false))
+                    }
+                />
+            }
+            if *show_stats {
+                <StatsScreen on_close={
+                    let show_stats = show_stats.clone();
+                    Callback::from(move |_| show_stats.set(false))
+                } />
+            }
+            if *show_leaderboard {
+                <LeaderboardScreen on_close={
+                    let show_leaderboard = show_leaderboard.clone();
+                    Callback::from(move |_| show_leaderboard.set(false))
+                } />
+            }
+            if *show_score_card {
+                <ScoreCardScreen
+                    info={CardInfo {
+                        score,
+                        best: *best_score,
+                        seed: *run_seed,
+                        url: challenge_url(*run_seed),
+                        path: world.borrow().birds[0].history.iter().copied().collect(),
+                        h: world.borrow().h,
+                    }}
+                    on_close={
+                        let show_score_card = show_score_card.clone();
+                        Callback::from(move |_| show_score_card.set(false))
+                    }
+                />
+            }
+            if *show_settings {
+                <SettingsScreen
+                    settings={(*settings).clone()}
+                    on_change={
+                        let settings = settings.clone();
+                        Callback::from(move |new_settings: Settings| {
+                            new_settings.save();
+                            settings.set(new_settings);
+                        })
+                    }
+                    on_close={
+                        let show_settings = show_settings.clone();
+                        Callback::from(move |_| show_settings.set(false))
+                    }
+                    custom_bird={(*custom_bird).clone()}
+                    on_custom_bird={
+                        clone_all![settings, custom_bird];
+                        Callback::from(move |bird: Option<String>| {
+                            custom_bird::save(&bird);
+                            if bird.is_some() {
+                                let mut new_settings = (*settings).clone();
+                                new_settings.skin = custom_bird::CUSTOM_SKIN.to_string();
+                                new_settings.save();
+                                settings.set(new_settings);
+                            }
+                            custom_bird.set(bird);
+                        })
+                    }
+                />
+            }
+            if *is_paused {
+                <div id="hint" class="no-select">
+                    <p>{ tr("Paused") }</p>
+                    // 练习时不会死，只能从这里回去
+                    if *mode == Mode::Practice {
+                        <button onclick={
+                            clone_all![is_paused, is_playing];
+                            Callback::from(move |_| {
+                                is_paused.set(false);
+                                is_playing.set(false);
+                            })
+                        }>
+                            { tr("Stop practice") }
+                        </button>
+                    }
+                </div>
+            }
+            { toasts.view() }
             if *comming_obstacles_distance > 0 {
-                <span id="next" class="no-select">{ *comming_obstacles_distance } { "m" }</span>
+                <span id="next" class="no-select">{ format_distance(*comming_obstacles_distance as f64) }</span>
             }
         </>
     }
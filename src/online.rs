@@ -0,0 +1,445 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, VecDeque};
+use std::rc::Rc;
+
+use flying_bird_protocol::{
+    BirdState, ClientMessage, PlayerInfo, ServerMessage, DEFAULT_PORT, PROTOCOL_VERSION,
+};
+use flying_bird_sim::BIRD_SIZE;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CloseEvent, Event, HtmlInputElement, MessageEvent, WebSocket};
+use yew::{function_component, html, Callback, Html, Properties, Reducible, TargetCast};
+
+use crate::i18n::{tr, trf};
+use crate::storage::{load_json, save_json};
+
+const STORAGE_KEY: &str = "online";
+
+// 联机时大家的世界一样高，赛道才一样，画的时候再缩放到屏幕
+pub const WORLD_H: f64 = BIRD_SIZE * 8.;
+// 每隔几帧发一次自己的状态
+pub const SEND_EVERY: u32 = 4;
+// 对手显示的是这么久之前的位置，这样总有两个快照可以插值
+const INTERP_DELAY: f64 = 100.;
+const SNAPSHOT_KEEP: f64 = 1000.;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnlineConfig {
+    pub server: String,
+    pub room: String,
+    pub name: String,
+}
+
+impl Default for OnlineConfig {
+    fn default() -> Self {
+        Self {
+            server: format!("ws://localhost:{DEFAULT_PORT}"),
+            room: "lobby".to_string(),
+            name: "Bird".to_string(),
+        }
+    }
+}
+
+impl OnlineConfig {
+    pub fn load() -> Self {
+        load_json(STORAGE_KEY)
+    }
+
+    pub fn save(&self) {
+        save_json(STORAGE_KEY, self);
+    }
+}
+
+pub enum NetEvent {
+    Message(ServerMessage),
+    Closed(String),
+}
+
+pub struct NetClient {
+    ws: WebSocket,
+    // 回调要和连接活得一样久
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl NetClient {
+    pub fn connect(config: &OnlineConfig, on_event: Callback<NetEvent>) -> Result<Self, JsValue> {
+        let ws = WebSocket::new(&config.server)?;
+
+        let hello = serde_json::to_string(&ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            room: config.room.clone(),
+            name: config.name.clone(),
+        })
+        .unwrap();
+        let on_open = {
+            let ws = ws.clone();
+            Closure::wrap(Box::new(move || {
+                let _ = ws.send_with_str(&hello);
+            }) as Box<dyn FnMut()>)
+        };
+        let on_message = {
+            let on_event = on_event.clone();
+            Closure::wrap(Box::new(move |e: MessageEvent| {
+                if let Some(msg) = e
+                    .data()
+                    .as_string()
+                    .and_then(|text| serde_json::from_str(&text).ok())
+                {
+                    on_event.emit(NetEvent::Message(msg));
+                }
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+        let on_close = Closure::wrap(Box::new(move |e: CloseEvent| {
+            let reason = if e.was_clean() {
+                tr("Disconnected").to_string()
+            } else {
+                trf("Connection lost ({code})", &[("code", &e.code())])
+            };
+            on_event.emit(NetEvent::Closed(reason));
+        }) as Box<dyn FnMut(CloseEvent)>);
+
+        ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            ws,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    }
+
+    pub fn send(&self, msg: &ClientMessage) {
+        if self.ws.ready_state() == WebSocket::OPEN {
+            let _ = self.ws.send_with_str(&serde_json::to_string(msg).unwrap());
+        }
+    }
+}
+
+impl Drop for NetClient {
+    fn drop(&mut self) {
+        self.ws.set_onopen(None);
+        self.ws.set_onmessage(None);
+        self.ws.set_onclose(None);
+        let _ = self.ws.close();
+    }
+}
+
+fn lerp(a: f64, b: f64, k: f64) -> f64 {
+    a + (b - a) * k
+}
+
+// 对手的鸟，收到的状态按本地收到的时间存下来
+// 一秒几十个，不放在Online里，不然每个都要重新渲染整个界面
+#[derive(Default)]
+pub struct Ghost {
+    snapshots: VecDeque<(f64, BirdState)>,
+}
+
+impl Ghost {
+    pub fn push(&mut self, now: f64, state: BirdState) {
+        self.snapshots.push_back((now, state));
+        while self.snapshots.len() > 2
+            && self
+                .snapshots
+                .front()
+                .is_some_and(|(t, _)| *t < now - SNAPSHOT_KEEP)
+        {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.snapshots.back().map(|(_, s)| s.score).unwrap_or(0)
+    }
+
+    pub fn sample(&self, now: f64) -> Option<BirdState> {
+        let t = now - INTERP_DELAY;
+        match self.snapshots.iter().position(|(st, _)| *st > t) {
+            None => self.snapshots.back().map(|(_, s)| *s),
+            Some(0) => self.snapshots.front().map(|(_, s)| *s),
+            Some(i) => {
+                let (t0, a) = self.snapshots[i - 1];
+                let (t1, b) = self.snapshots[i];
+                let k = (t - t0) / (t1 - t0);
+                Some(BirdState {
+                    x: lerp(a.x, b.x, k),
+                    pos: lerp(a.pos, b.pos, k),
+                    angle: lerp(a.angle, b.angle, k),
+                    score: a.score,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    Offline,
+    Connecting,
+    InRoom,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct Online {
+    pub status: Status,
+    pub error: Option<String>,
+    pub id: u32,
+    pub room: String,
+    pub players: Vec<PlayerInfo>,
+    // 这一局撞了的对手
+    pub crashed: BTreeSet<u32>,
+    // 每开一局加一
+    pub round: u32,
+    pub seed: u64,
+    // 上一局的(名字, 得分)，高的在前
+    pub results: Vec<(String, u32)>,
+}
+
+impl Online {
+    pub fn name_of(&self, id: u32) -> &str {
+        self.players
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.name.as_str())
+            .unwrap_or("?")
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.players.iter().any(|p| p.id == self.id && p.ready)
+    }
+}
+
+pub enum OnlineAction {
+    Connecting,
+    Event(NetEvent),
+    Disconnect,
+}
+
+impl Reducible for Online {
+    type Action = OnlineAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut online = (*self).clone();
+        match action {
+            OnlineAction::Connecting => {
+                online = Online {
+                    status: Status::Connecting,
+                    ..Default::default()
+                };
+            }
+            OnlineAction::Disconnect => online = Online::default(),
+            OnlineAction::Event(NetEvent::Closed(reason)) => {
+                online = Online {
+                    error: Some(online.error.unwrap_or(reason)),
+                    ..Default::default()
+                };
+            }
+            OnlineAction::Event(NetEvent::Message(msg)) => match msg {
+                ServerMessage::Welcome {
+                    id, room, players, ..
+                } => {
+                    online.status = Status::InRoom;
+                    online.id = id;
+                    online.room = room;
+                    online.players = players;
+                }
+                ServerMessage::Error { message } => online.error = Some(message),
+                ServerMessage::Joined { player } => online.players.push(player),
+                ServerMessage::Left { id } => {
+                    online.players.retain(|p| p.id != id);
+                    online.crashed.remove(&id);
+                }
+                ServerMessage::Ready { id } => {
+                    if let Some(p) = online.players.iter_mut().find(|p| p.id == id) {
+                        p.ready = true;
+                    }
+                }
+                ServerMessage::Start { seed } => {
+                    online.round += 1;
+                    online.seed = seed;
+                    for p in online.players.iter_mut() {
+                        p.ready = false;
+                    }
+                    online.crashed.clear();
+                }
+                // 对手的位置在App里直接记到Ghost上
+                ServerMessage::State { .. } => {}
+                ServerMessage::Crashed { id, .. } => {
+                    online.crashed.insert(id);
+                }
+                ServerMessage::RoundOver { mut scores } => {
+                    scores.sort_by_key(|&(_, score)| Reverse(score));
+                    online.results = scores
+                        .into_iter()
+                        .map(|(id, score)| (online.name_of(id).to_string(), score))
+                        .collect();
+                }
+            },
+        }
+        Rc::new(online)
+    }
+}
+
+fn text_input(label: &'static str, value: &str, oninput: Callback<String>) -> Html {
+    let oninput = Callback::from(move |e: Event| {
+        oninput.emit(e.target_unchecked_into::<HtmlInputElement>().value())
+    });
+    html! {
+        <label class="setting">
+            { tr(label) }
+            <input type="text" value={value.to_string()} onchange={oninput} />
+        </label>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct OnlinePanelProps {
+    pub online: Online,
+    pub config: OnlineConfig,
+    pub on_config: Callback<OnlineConfig>,
+    pub on_connect: Callback<()>,
+    pub on_disconnect: Callback<()>,
+}
+
+#[function_component(OnlinePanel)]
+pub fn online_panel(props: &OnlinePanelProps) -> Html {
+    let OnlinePanelProps {
+        online,
+        config,
+        on_config,
+        on_connect,
+        on_disconnect,
+    } = props;
+
+    let update = |f: fn(&mut OnlineConfig, String)| {
+        let config = config.clone();
+        let on_config = on_config.clone();
+        Callback::from(move |v: String| {
+            let mut config = config.clone();
+            f(&mut config, v);
+            on_config.emit(config);
+        })
+    };
+
+    html! {
+        <div id="online" class="no-select">
+            if online.status == Status::InRoom {
+                <h3>{ trf("Room \"{room}\"", &[("room", &online.room)]) }</h3>
+                <ul class="room-players">
+                    { for online.players.iter().map(|p| html! {
+                        <li>
+                            { &p.name }
+                            if p.id == online.id { { tr(" (you)") } }
+                            if p.ready { { " ✓" } }
+                        </li>
+                    }) }
+                </ul>
+                if !online.results.is_empty() {
+                    <p>
+                        { tr("Last round: ") }
+                        { online.results.iter().map(|(name, score)| format!("{name} {score}")).collect::<Vec<_>>().join(" · ") }
+                    </p>
+                }
+                <button onclick={on_disconnect.reform(|_| ())}>{ tr("Leave") }</button>
+            } else {
+                { text_input("Server", &config.server, update(|c, v| c.server = v)) }
+                { text_input("Room", &config.room, update(|c, v| c.room = v)) }
+                { text_input("Name", &config.name, update(|c, v| c.name = v)) }
+                <button
+                    disabled={online.status == Status::Connecting}
+                    onclick={on_connect.reform(|_| ())}
+                >
+                    { tr(if online.status == Status::Connecting { "Connecting…" } else { "Join" }) }
+                </button>
+            }
+            if let Some(error) = &online.error {
+                <p class="error">{ error }</p>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: f64, score: u32) -> BirdState {
+        BirdState {
+            x,
+            pos: x / 2.,
+            angle: x / 100.,
+            score,
+        }
+    }
+
+    // 在0和100毫秒收到x=0和x=100
+    fn ghost() -> Ghost {
+        let mut ghost = Ghost::default();
+        ghost.push(0., state(0., 1));
+        ghost.push(100., state(100., 2));
+        ghost
+    }
+
+    #[test]
+    fn empty_ghost_has_nothing_to_draw() {
+        assert_eq!(Ghost::default().sample(0.), None);
+        assert_eq!(Ghost::default().score(), 0);
+    }
+
+    #[test]
+    fn clamps_outside_the_snapshots() {
+        let ghost = ghost();
+        assert_eq!(ghost.sample(INTERP_DELAY - 50.), Some(state(0., 1)));
+        assert_eq!(ghost.sample(INTERP_DELAY), Some(state(0., 1)));
+        assert_eq!(ghost.sample(INTERP_DELAY + 100.), Some(state(100., 2)));
+        assert_eq!(ghost.sample(INTERP_DELAY + 500.), Some(state(100., 2)));
+    }
+
+    #[test]
+    fn interpolates_between_snapshots() {
+        let sample = ghost().sample(INTERP_DELAY + 50.).unwrap();
+        assert_eq!(sample.x, 50.);
+        assert_eq!(sample.pos, 25.);
+        assert_eq!(sample.angle, 0.5);
+        // 分数不插值，用前一个的
+        assert_eq!(sample.score, 1);
+        assert_eq!(ghost().score(), 2);
+    }
+
+    #[test]
+    fn trims_old_snapshots() {
+        let mut ghost = Ghost::default();
+        for i in 0..100 {
+            ghost.push(i as f64 * 100., state(i as f64, i));
+        }
+        let now = 99. * 100.;
+        assert!(ghost
+            .snapshots
+            .iter()
+            .all(|(t, _)| *t >= now - SNAPSHOT_KEEP));
+        assert_eq!(ghost.snapshots.len(), 11);
+    }
+
+    #[test]
+    fn keeps_two_snapshots_after_a_long_gap() {
+        let mut ghost = Ghost::default();
+        for i in 0..5 {
+            ghost.push(i as f64 * SNAPSHOT_KEEP * 10., state(i as f64, i));
+        }
+        // 很久才来一个也还有两个可以插值
+        assert_eq!(ghost.snapshots.len(), 2);
+        let now = 4. * SNAPSHOT_KEEP * 10.;
+        let sample = ghost
+            .sample(now - SNAPSHOT_KEEP * 5. + INTERP_DELAY)
+            .unwrap();
+        assert_eq!(sample.x, 3.5);
+    }
+}
@@ -0,0 +1,229 @@
+use std::f64::consts::TAU;
+
+use flying_bird_sim::BIRD_SIZE;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use web_sys::CanvasRenderingContext2d;
+
+// 同时存在的粒子上限，画质低时少一些
+const MAX_PARTICLES: usize = 300;
+const LOW_QUALITY_MAX: usize = 60;
+// 画质低时每次只放这么多份之一
+const LOW_QUALITY_DIVISOR: usize = 4;
+
+const FEATHERS: usize = 24;
+const SPARKLES: usize = 40;
+// 鸟的中心离地面多近开始扬尘，每帧扬几粒
+const DUST_HEIGHT: f64 = BIRD_SIZE / 2. + 40.;
+const DUST_PER_STEP: usize = 2;
+// 速度超过这个就画风线
+const SPEED_LINE_V: f64 = 8.;
+const GRAVITY: f64 = 0.15;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Feather,
+    Dust,
+    Sparkle,
+    SpeedLine,
+}
+
+struct Particle {
+    kind: Kind,
+    // 世界坐标
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    angle: f64,
+    spin: f64,
+    age: u32,
+    life: u32,
+}
+
+// 只是好看，随机数不用世界里的那个，回放还是一样的
+pub struct Particles {
+    list: Vec<Particle>,
+    rng: StdRng,
+    low_quality: bool,
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Self {
+            list: vec![],
+            rng: StdRng::from_entropy(),
+            low_quality: false,
+        }
+    }
+}
+
+impl Particles {
+    pub fn reset(&mut self) {
+        self.list.clear();
+    }
+
+    pub fn set_low_quality(&mut self, low_quality: bool) {
+        self.low_quality = low_quality;
+    }
+
+    fn spawn(&mut self, n: usize, mut make: impl FnMut(&mut StdRng) -> Particle) {
+        let (cap, n) = if self.low_quality {
+            (LOW_QUALITY_MAX, n.div_ceil(LOW_QUALITY_DIVISOR))
+        } else {
+            (MAX_PARTICLES, n)
+        };
+        let n = n.min(cap - self.list.len().min(cap));
+        for _ in 0..n {
+            let particle = make(&mut self.rng);
+            self.list.push(particle);
+        }
+    }
+
+    // 撞了掉一地羽毛
+    pub fn crash(&mut self, x: f64, y: f64) {
+        self.spawn(FEATHERS, |rng| {
+            let (sin, cos) = rng.gen_range(0. ..TAU).sin_cos();
+            let v = rng.gen_range(2. ..6.);
+            Particle {
+                kind: Kind::Feather,
+                x,
+                y,
+                vx: v * cos,
+                vy: v * sin - 2.,
+                angle: rng.gen_range(0. ..TAU),
+                spin: rng.gen_range(-0.2..0.2),
+                age: 0,
+                life: rng.gen_range(60..120),
+            }
+        });
+    }
+
+    // 贴着地面飞的时候扬起灰尘
+    pub fn skim(&mut self, x: f64, y: f64, floor: f64) {
+        if floor - y > DUST_HEIGHT {
+            return;
+        }
+        self.spawn(DUST_PER_STEP, |rng| Particle {
+            kind: Kind::Dust,
+            x: x + rng.gen_range(-20. ..20.),
+            y: floor - 4.,
+            vx: rng.gen_range(-1.5..0.),
+            vy: rng.gen_range(-1.5..-0.3),
+            angle: 0.,
+            spin: 0.,
+            age: 0,
+            life: rng.gen_range(20..40),
+        });
+    }
+
+    pub fn best(&mut self, x: f64, y: f64) {
+        self.spawn(SPARKLES, |rng| {
+            let (sin, cos) = rng.gen_range(0. ..TAU).sin_cos();
+            let v = rng.gen_range(3. ..8.);
+            Particle {
+                kind: Kind::Sparkle,
+                x,
+                y,
+                vx: v * cos,
+                vy: v * sin,
+                angle: rng.gen_range(0. ..TAU),
+                spin: 0.3,
+                age: 0,
+                life: rng.gen_range(30..60),
+            }
+        });
+    }
+
+    // 风线不动，鸟飞过去看着就是往后退
+    pub fn speed_lines(&mut self, x: f64, y: f64, v: f64) {
+        if v < SPEED_LINE_V {
+            return;
+        }
+        self.spawn(1, |rng| Particle {
+            kind: Kind::SpeedLine,
+            x: x + rng.gen_range(0. ..200.),
+            y: y + rng.gen_range(-150. ..150.),
+            vx: 0.,
+            vy: 0.,
+            angle: 0.,
+            spin: 0.,
+            age: 0,
+            life: 20,
+        });
+    }
+
+    pub fn step(&mut self) {
+        for p in self.list.iter_mut() {
+            p.x += p.vx;
+            p.y += p.vy;
+            p.angle += p.spin;
+            match p.kind {
+                // 羽毛飘着落，左右晃
+                Kind::Feather => {
+                    p.vx *= 0.95;
+                    p.vy = (p.vy + GRAVITY).min(1.5);
+                    p.x += p.angle.sin();
+                }
+                Kind::Dust => p.vx *= 0.9,
+                Kind::Sparkle => {
+                    p.vx *= 0.92;
+                    p.vy *= 0.92;
+                }
+                Kind::SpeedLine => {}
+            }
+            p.age += 1;
+        }
+        self.list.retain(|p| p.age < p.life);
+    }
+
+    // dx: 世界坐标到屏幕坐标的偏移
+    pub fn draw(&self, ctx: &CanvasRenderingContext2d, dx: f64) {
+        ctx.save();
+        for p in self.list.iter() {
+            let fade = 1. - p.age as f64 / p.life as f64;
+            ctx.set_global_alpha(fade);
+            let (x, y) = (p.x + dx, p.y);
+            match p.kind {
+                Kind::Feather => {
+                    ctx.save();
+                    ctx.translate(x, y).unwrap();
+                    ctx.rotate(p.angle).unwrap();
+                    ctx.set_fill_style_str("rgb(250, 250, 250)");
+                    ctx.set_stroke_style_str("rgb(60, 60, 60)");
+                    ctx.set_line_width(1.5);
+                    ctx.begin_path();
+                    let _ = ctx.ellipse(0., 0., 12., 4., 0., 0., TAU);
+                    ctx.fill();
+                    ctx.stroke();
+                    ctx.restore();
+                }
+                Kind::Dust => {
+                    ctx.set_fill_style_str("rgb(170, 160, 140)");
+                    ctx.begin_path();
+                    let _ = ctx.arc(x, y, 3. + 6. * (1. - fade), 0., TAU);
+                    ctx.fill();
+                }
+                Kind::Sparkle => {
+                    ctx.set_fill_style_str("rgb(255, 200, 40)");
+                    let r = 6. * fade.sqrt();
+                    ctx.save();
+                    ctx.translate(x, y).unwrap();
+                    ctx.rotate(p.angle).unwrap();
+                    ctx.fill_rect(-r, -r / 4., 2. * r, r / 2.);
+                    ctx.fill_rect(-r / 4., -r, r / 2., 2. * r);
+                    ctx.restore();
+                }
+                Kind::SpeedLine => {
+                    ctx.set_stroke_style_str("rgb(150, 150, 150)");
+                    ctx.set_line_width(2.);
+                    ctx.begin_path();
+                    ctx.move_to(x, y);
+                    ctx.line_to(x + 80., y);
+                    ctx.stroke();
+                }
+            }
+        }
+        ctx.restore();
+    }
+}
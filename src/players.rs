@@ -0,0 +1,54 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    Single,
+    // 同一台设备上两个人，同一串障碍物
+    TwoPlayer,
+    // 通过中继服务器和别人比赛
+    Online,
+    // 撞了不死，可以慢放、从中间开始，不记成绩
+    Practice,
+}
+
+impl Mode {
+    pub fn players(&self) -> usize {
+        match self {
+            Mode::Single | Mode::Online | Mode::Practice => 1,
+            Mode::TwoPlayer => 2,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Mode::Single => Mode::TwoPlayer,
+            Mode::TwoPlayer => Mode::Online,
+            Mode::Online => Mode::Practice,
+            Mode::Practice => Mode::Single,
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Mode::Single => "👤",
+            Mode::TwoPlayer => "👥",
+            Mode::Online => "🌐",
+            Mode::Practice => "🎯",
+        }
+    }
+}
+
+pub struct Player {
+    pub name: &'static str,
+    // css class，和画面上的颜色对应
+    pub class: &'static str,
+}
+
+pub const PLAYERS: [Player; 2] = [
+    Player {
+        name: "P1",
+        class: "p1",
+    },
+    Player {
+        name: "P2",
+        class: "p2",
+    },
+];
@@ -0,0 +1,169 @@
+use flying_bird_sim::{World, MAX_SKIP};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::{function_component, html, Callback, Event, Html, InputEvent, Properties, TargetCast};
+
+use crate::i18n::{tr, trf};
+use crate::storage::{load_json, save_json};
+use crate::INTERV;
+
+const STORAGE_KEY: &str = "practice";
+// 速度百分比的范围
+const MIN_SPEED: u32 = 25;
+const MAX_SPEED: u32 = 100;
+const SPEED_STEP: u32 = 5;
+// 撞上的障碍物闪几帧，闪成红色
+pub const FLASH_STEPS: u32 = 20;
+pub const HIT_FLASH: (u8, u8, u8) = (220, 40, 40);
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PracticeConfig {
+    pub seed: u64,
+    // 从第几个障碍物开始
+    pub start: u32,
+    // 飞过几个障碍物就回到开头，0是不循环
+    pub loop_len: u32,
+    pub speed: u32,
+}
+
+impl Default for PracticeConfig {
+    fn default() -> Self {
+        Self {
+            seed: thread_rng().gen(),
+            start: 0,
+            loop_len: 0,
+            speed: MAX_SPEED,
+        }
+    }
+}
+
+impl PracticeConfig {
+    pub fn load() -> Self {
+        load_json(STORAGE_KEY)
+    }
+
+    pub fn save(&self) {
+        save_json(STORAGE_KEY, self);
+    }
+
+    pub fn world(&self, w: f64, h: f64) -> World {
+        let mut world = World::new(w, h, 1, self.seed);
+        world.invincible = true;
+        world.skip_to(self.start.min(MAX_SKIP));
+        world
+    }
+
+    // 毫秒
+    pub fn frame_interval(&self) -> u32 {
+        INTERV * MAX_SPEED / self.speed.clamp(MIN_SPEED, MAX_SPEED)
+    }
+
+    // 飞过这一段了，该回到开头
+    pub fn section_done(&self, score: u32) -> bool {
+        self.loop_len > 0 && score >= self.start.min(MAX_SKIP) + self.loop_len.min(MAX_SKIP)
+    }
+}
+
+fn number_input(label: &'static str, value: u64, max: u64, onchange: Callback<u64>) -> Html {
+    let onchange = Callback::from(move |e: Event| {
+        if let Ok(v) = e
+            .target_unchecked_into::<HtmlInputElement>()
+            .value()
+            .parse()
+        {
+            onchange.emit(max.min(v));
+        }
+    });
+    html! {
+        <label class="setting">
+            { tr(label) }
+            <input type="number" min="0" max={max.to_string()} value={value.to_string()} {onchange} />
+        </label>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PracticePanelProps {
+    pub config: PracticeConfig,
+    pub on_change: Callback<PracticeConfig>,
+}
+
+#[function_component(PracticePanel)]
+pub fn practice_panel(props: &PracticePanelProps) -> Html {
+    let PracticePanelProps { config, on_change } = props;
+
+    let update = |f: fn(&mut PracticeConfig, u64)| {
+        let config = config.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |v: u64| {
+            let mut config = config.clone();
+            f(&mut config, v);
+            on_change.emit(config);
+        })
+    };
+
+    html! {
+        <div id="practice" class="no-select">
+            <h3>{ tr("Practice") }</h3>
+            <p>{ tr("Crashes are free here and nothing is recorded.") }</p>
+            <div class="setting">
+                { number_input("Course", config.seed, u64::MAX, update(|c, v| c.seed = v)) }
+                <button onclick={update(|c, v| c.seed = v).reform(|_| thread_rng().gen())}>
+                    { "🎲" }
+                </button>
+            </div>
+            { number_input("Start at obstacle", config.start as u64, MAX_SKIP as u64, update(|c, v| c.start = v as u32)) }
+            { number_input("Loop every (obstacles, 0 = off)", config.loop_len as u64, MAX_SKIP as u64, update(|c, v| c.loop_len = v as u32)) }
+            <label class="setting">
+                { trf("Game speed {speed}%", &[("speed", &config.speed)]) }
+                <input
+                    type="range"
+                    min={MIN_SPEED.to_string()}
+                    max={MAX_SPEED.to_string()}
+                    step={SPEED_STEP.to_string()}
+                    value={config.speed.to_string()}
+                    oninput={update(|c, v| c.speed = v as u32).reform(|e: InputEvent| {
+                        e.target_unchecked_into::<HtmlInputElement>().value().parse().unwrap_or(MAX_SPEED as u64)
+                    })}
+                />
+            </label>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(start: u32, loop_len: u32) -> PracticeConfig {
+        PracticeConfig {
+            seed: 1,
+            start,
+            loop_len,
+            speed: MAX_SPEED,
+        }
+    }
+
+    #[test]
+    fn section_done_after_loop_len_obstacles() {
+        let config = config(5, 3);
+        assert!(!config.section_done(5));
+        assert!(!config.section_done(7));
+        assert!(config.section_done(8));
+    }
+
+    #[test]
+    fn no_loop_when_loop_len_is_zero() {
+        assert!(!config(5, 0).section_done(u32::MAX));
+    }
+
+    #[test]
+    fn huge_values_do_not_overflow() {
+        let config = config(u32::MAX, u32::MAX);
+        assert!(!config.section_done(MAX_SKIP));
+        assert!(config.section_done(2 * MAX_SKIP));
+        assert_eq!(config.world(1920., 1080.).birds[0].score, MAX_SKIP);
+    }
+}
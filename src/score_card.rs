@@ -0,0 +1,360 @@
+use js_sys::{Array, Reflect};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    window, Blob, CanvasRenderingContext2d, File, FilePropertyBag, HtmlCanvasElement, ShareData,
+    UrlSearchParams,
+};
+use yew::{function_component, html, use_effect_with, use_state, Callback, Html, Properties};
+
+use crate::download::download_url;
+use crate::i18n::{tr, trf};
+
+pub const CARD_W: f64 = 1200.;
+pub const CARD_H: f64 = 630.;
+const MARGIN: f64 = 60.;
+// 右边放轨迹的小图
+const THUMB: Rect = Rect {
+    x: 620.,
+    y: 110.,
+    w: 520.,
+    h: 360.,
+};
+const BG: &str = "rgb(240, 240, 240)";
+const INK: &str = "rgb(40, 40, 40)";
+const FAINT: &str = "rgb(120, 120, 120)";
+const THUMB_BG: &str = "rgb(255, 255, 255)";
+const FILE_NAME: &str = "flying-bird.png";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+// 排版只算出要画什么，真正画的时候再一条条交给canvas
+#[derive(Debug, PartialEq)]
+pub enum Item {
+    Fill(Rect, &'static str),
+    Text {
+        x: f64,
+        y: f64,
+        size: f64,
+        text: String,
+        color: &'static str,
+    },
+    Line {
+        points: Vec<(f64, f64)>,
+        color: &'static str,
+        width: f64,
+    },
+}
+
+#[derive(Clone, PartialEq)]
+pub struct CardInfo {
+    pub score: u32,
+    pub best: u32,
+    pub seed: u64,
+    pub url: String,
+    // 轨迹，世界坐标，最新的在前面
+    pub path: Vec<(f64, f64)>,
+    // 世界的高度，小图上下对应天花板和地面
+    pub h: f64,
+}
+
+// 链接里带着种子，打开就是同一条赛道
+pub fn challenge_seed() -> Option<u64> {
+    let search = window().unwrap().location().search().ok()?;
+    UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get("seed")?
+        .parse()
+        .ok()
+}
+
+pub fn challenge_url(seed: u64) -> String {
+    let location = window().unwrap().location();
+    format!(
+        "{}{}?seed={seed}",
+        location.origin().unwrap_or_default(),
+        location.pathname().unwrap_or_default()
+    )
+}
+
+// 保持比例缩进box里，左右居中
+fn fit_path(path: &[(f64, f64)], h: f64, rect: Rect) -> Vec<(f64, f64)> {
+    let Some(min_x) = path.iter().map(|p| p.0).reduce(f64::min) else {
+        return vec![];
+    };
+    let max_x = path.iter().map(|p| p.0).fold(min_x, f64::max);
+    let scale = (rect.w / (max_x - min_x).max(1.)).min(rect.h / h);
+    let left = rect.x + (rect.w - (max_x - min_x) * scale) / 2.;
+    let top = rect.y + (rect.h - h * scale) / 2.;
+    path.iter()
+        .rev()
+        .map(|(x, y)| (left + (x - min_x) * scale, top + y.clamp(0., h) * scale))
+        .collect()
+}
+
+pub fn layout(info: &CardInfo) -> Vec<Item> {
+    let text = |x, y, size, text: String, color| Item::Text {
+        x,
+        y,
+        size,
+        text,
+        color,
+    };
+    let mut items = vec![
+        Item::Fill(
+            Rect {
+                x: 0.,
+                y: 0.,
+                w: CARD_W,
+                h: CARD_H,
+            },
+            BG,
+        ),
+        text(MARGIN, 110., 56., "Flying Bird".to_string(), INK),
+        text(MARGIN, 210., 32., tr("Score").to_string(), FAINT),
+        text(MARGIN, 330., 120., format!("{:0>4}", info.score), INK),
+        text(MARGIN, 410., 32., tr("Best").to_string(), FAINT),
+        text(MARGIN, 470., 56., format!("{:0>4}", info.best), INK),
+        Item::Fill(THUMB, THUMB_BG),
+        text(
+            MARGIN,
+            CARD_H - 80.,
+            28.,
+            trf("Seed {seed}", &[("seed", &info.seed)]),
+            FAINT,
+        ),
+        text(MARGIN, CARD_H - 40., 28., info.url.clone(), INK),
+    ];
+    let points = fit_path(&info.path, info.h, THUMB);
+    if points.len() > 1 {
+        items.push(Item::Line {
+            points,
+            color: INK,
+            width: 4.,
+        });
+    }
+    items
+}
+
+pub fn render(items: &[Item]) -> HtmlCanvasElement {
+    let canvas: HtmlCanvasElement = window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_element("canvas")
+        .unwrap()
+        .unchecked_into();
+    canvas.set_width(CARD_W as u32);
+    canvas.set_height(CARD_H as u32);
+    let ctx: CanvasRenderingContext2d = canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+    for item in items {
+        match item {
+            Item::Fill(rect, color) => {
+                ctx.set_fill_style_str(color);
+                ctx.fill_rect(rect.x, rect.y, rect.w, rect.h);
+            }
+            Item::Text {
+                x,
+                y,
+                size,
+                text,
+                color,
+            } => {
+                ctx.set_fill_style_str(color);
+                ctx.set_font(&format!("bold {size}px sans-serif"));
+                ctx.fill_text(text, *x, *y).unwrap();
+            }
+            Item::Line {
+                points,
+                color,
+                width,
+            } => {
+                ctx.set_stroke_style_str(color);
+                ctx.set_line_width(*width);
+                ctx.set_line_join("round");
+                ctx.begin_path();
+                for (x, y) in points {
+                    ctx.line_to(*x, *y);
+                }
+                ctx.stroke();
+            }
+        }
+    }
+    canvas
+}
+
+// 不支持分享文件的浏览器不显示分享按钮
+fn share_data(file: &File) -> Option<ShareData> {
+    let navigator = window().unwrap().navigator();
+    if !Reflect::has(&navigator, &JsValue::from_str("canShare")).unwrap_or(false) {
+        return None;
+    }
+    let data = ShareData::new();
+    data.set_files(&Array::of1(file));
+    navigator.can_share_with_data(&data).then_some(data)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ScoreCardProps {
+    pub info: CardInfo,
+    pub on_close: Callback<()>,
+}
+
+#[function_component(ScoreCardScreen)]
+pub fn score_card_screen(props: &ScoreCardProps) -> Html {
+    let ScoreCardProps { info, on_close } = props;
+    let image = use_state(|| None::<String>);
+    let file = use_state(|| None::<File>);
+
+    {
+        let image = image.clone();
+        let file = file.clone();
+        use_effect_with(info.clone(), move |info| {
+            let canvas = render(&layout(info));
+            image.set(canvas.to_data_url_with_type("image/png").ok());
+            // 分享要的是文件，先转好
+            let on_blob = Closure::once_into_js(move |blob: Option<Blob>| {
+                let options = FilePropertyBag::new();
+                options.set_type("image/png");
+                file.set(blob.and_then(|blob| {
+                    File::new_with_blob_sequence_and_options(
+                        &Array::of1(&blob),
+                        FILE_NAME,
+                        &options,
+                    )
+                    .ok()
+                }));
+            });
+            let _ = canvas.to_blob(on_blob.unchecked_ref());
+        });
+    }
+
+    let share = (*file).as_ref().and_then(|file| {
+        let data = share_data(file)?;
+        data.set_url(&info.url);
+        Some(Callback::from(move |_| {
+            let _ = window().unwrap().navigator().share_with_data(&data);
+        }))
+    });
+
+    html! {
+        <div class="screen">
+            <div class="screen-header">
+                <h2>{ tr("Score card") }</h2>
+                <button onclick={on_close.reform(|_| ())}>{ "✕" }</button>
+            </div>
+            if let Some(src) = (*image).clone() {
+                <img class="score-card" src={src.clone()} />
+                <button onclick={Callback::from(move |_| download_url(FILE_NAME, &src))}>
+                    { tr("Download") }
+                </button>
+            }
+            if let Some(onclick) = share {
+                <button {onclick}>{ tr("Share") }</button>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl Rect {
+        fn contains(&self, (x, y): (f64, f64)) -> bool {
+            self.x <= x && x <= self.x + self.w && self.y <= y && y <= self.y + self.h
+        }
+    }
+
+    fn info(path: Vec<(f64, f64)>) -> CardInfo {
+        CardInfo {
+            score: 42,
+            best: 108,
+            seed: 12345,
+            url: "https://example.com/?seed=12345".to_string(),
+            path,
+            h: 1000.,
+        }
+    }
+
+    fn texts(items: &[Item]) -> Vec<&str> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn line(items: &[Item]) -> Option<&Vec<(f64, f64)>> {
+        items.iter().find_map(|item| match item {
+            Item::Line { points, .. } => Some(points),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn shows_scores_seed_and_url() {
+        let items = layout(&info(vec![]));
+        let texts = texts(&items);
+        assert!(texts.contains(&"0042"));
+        assert!(texts.contains(&"0108"));
+        assert!(texts.contains(&"Seed 12345"));
+        assert!(texts.contains(&"https://example.com/?seed=12345"));
+    }
+
+    #[test]
+    fn everything_stays_on_the_card() {
+        let card = Rect {
+            x: 0.,
+            y: 0.,
+            w: CARD_W,
+            h: CARD_H,
+        };
+        for item in layout(&info(vec![(0., 0.), (5000., 1000.)])) {
+            match item {
+                Item::Fill(rect, _) => {
+                    assert!(card.contains((rect.x, rect.y)));
+                    assert!(card.contains((rect.x + rect.w, rect.y + rect.h)));
+                }
+                Item::Text { x, y, size, .. } => assert!(card.contains((x, y - size))),
+                Item::Line { points, .. } => assert!(points.iter().all(|p| card.contains(*p))),
+            }
+        }
+    }
+
+    #[test]
+    fn path_fits_thumbnail_and_keeps_its_shape() {
+        // history里最新的在前面
+        let path = vec![(900., 800.), (500., 200.), (100., 500.)];
+        let items = layout(&info(path));
+        let points = line(&items).unwrap();
+        assert!(points.iter().all(|p| THUMB.contains(*p)));
+        // 从旧到新画，左边是最早的点
+        assert!(points[0].0 < points[2].0);
+        let (sx, sy) = (
+            (points[2].0 - points[0].0) / 800.,
+            (points[1].1 - points[2].1) / -600.,
+        );
+        assert!((sx - sy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn out_of_bounds_points_are_clamped() {
+        let points = fit_path(&[(0., -50.), (10., 2000.)], 1000., THUMB);
+        assert!(points.iter().all(|p| THUMB.contains(*p)));
+    }
+
+    #[test]
+    fn no_line_without_a_path() {
+        assert!(line(&layout(&info(vec![]))).is_none());
+        assert!(line(&layout(&info(vec![(10., 10.)]))).is_none());
+    }
+}
@@ -0,0 +1,456 @@
+use serde::{Deserialize, Serialize};
+use web_sys::{
+    HtmlElement, HtmlInputElement, HtmlSelectElement, KeyboardEvent, MouseEvent, PointerEvent,
+};
+use yew::{
+    classes, function_component, html, use_effect_with, use_node_ref, use_state, Callback, Html,
+    Properties, TargetCast,
+};
+
+use crate::custom_bird::{CustomBird, CUSTOM_SKIN};
+use crate::i18n::{tr, trf, Language};
+use crate::input::{Action, Binding, Bindings};
+use crate::skins::{Skin, SKINS};
+use crate::storage::{load_json, save_json};
+use crate::INTERV;
+
+const STORAGE_KEY: &str = "settings";
+
+// 慢下来就是每一帧隔得久一点，飞行轨迹不变
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GameSpeed {
+    #[default]
+    Normal,
+    Slow,
+    Slower,
+}
+
+impl GameSpeed {
+    const ALL: [GameSpeed; 3] = [GameSpeed::Normal, GameSpeed::Slow, GameSpeed::Slower];
+
+    fn id(&self) -> &'static str {
+        match self {
+            GameSpeed::Normal => "normal",
+            GameSpeed::Slow => "slow",
+            GameSpeed::Slower => "slower",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            GameSpeed::Normal => "100%",
+            GameSpeed::Slow => "80%",
+            GameSpeed::Slower => "50%",
+        }
+    }
+
+    // 毫秒
+    pub fn frame_interval(&self) -> u32 {
+        match self {
+            GameSpeed::Normal => INTERV,
+            GameSpeed::Slow => INTERV * 5 / 4,
+            GameSpeed::Slower => INTERV * 2,
+        }
+    }
+}
+
+// 撞了以后怎么办
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Rules {
+    // 从头再来
+    #[default]
+    Classic,
+    // 花一条命退回几秒前接着飞，分数留着
+    Rewind,
+}
+
+impl Rules {
+    const ALL: [Rules; 2] = [Rules::Classic, Rules::Rewind];
+
+    fn id(&self) -> &'static str {
+        match self {
+            Rules::Classic => "classic",
+            Rules::Rewind => "rewind",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Rules::Classic => "Classic: start over after a crash",
+            Rules::Rewind => "Rewind: spend a life to go back 3 seconds",
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // 手柄扳机按下的程度决定爬升速度
+    pub analog_triggers: bool,
+    pub muted: bool,
+    pub bindings: Bindings,
+    pub speed: GameSpeed,
+    // 轨迹短一点，得分文字不飘
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    // 快到下一个障碍物时嘀几声
+    pub obstacle_cues: bool,
+    // 按一下开始爬升，再按一下停，只有一个开关也能玩
+    pub toggle_fly: bool,
+    pub language: Language,
+    // 距离用英尺显示
+    pub imperial: bool,
+    pub skin: String,
+    // 少放一些粒子
+    pub low_quality: bool,
+    pub rules: Rules,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings: Self = load_json(STORAGE_KEY);
+        settings.bindings.fill_missing();
+        settings
+    }
+
+    pub fn save(&self) {
+        save_json(STORAGE_KEY, self);
+    }
+}
+
+fn checkbox(label: &'static str, checked: bool, onchange: Callback<bool>) -> Html {
+    let onchange = Callback::from(move |e: yew::Event| {
+        onchange.emit(e.target_unchecked_into::<HtmlInputElement>().checked())
+    });
+    html! {
+        <label class="setting">
+            <input type="checkbox" {checked} {onchange} />
+            { tr(label) }
+        </label>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct CaptureProps {
+    action: Action,
+    on_capture: Callback<Binding>,
+    on_cancel: Callback<()>,
+}
+
+// 等待玩家按下要绑定的键
+#[function_component(Capture)]
+fn capture(props: &CaptureProps) -> Html {
+    let area_ref = use_node_ref();
+    use_effect_with(area_ref.clone(), |area_ref| {
+        if let Some(area) = area_ref.cast::<HtmlElement>() {
+            area.focus().unwrap();
+        }
+    });
+
+    let onkeydown = {
+        let on_capture = props.on_capture.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if let Some(binding) = Binding::from_key(&e) {
+                e.prevent_default();
+                on_capture.emit(binding);
+            }
+        })
+    };
+    let onpointerdown = {
+        let on_capture = props.on_capture.clone();
+        Callback::from(move |e: PointerEvent| {
+            e.prevent_default();
+            on_capture.emit(Binding::from_pointer(&e));
+        })
+    };
+
+    html! {
+        <div class="capture">
+            <div
+                class="capture-area"
+                tabindex="0"
+                ref={area_ref}
+                {onkeydown}
+                {onpointerdown}
+                oncontextmenu={Callback::from(|e: MouseEvent| e.prevent_default())}
+            >
+                { trf("Press a key, click or tap here to bind \"{action}\"", &[("action", &props.action.name())]) }
+            </div>
+            <button onclick={props.on_cancel.reform(|_| ())}>{ tr("Cancel") }</button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsScreenProps {
+    pub settings: Settings,
+    pub on_change: Callback<Settings>,
+    pub on_close: Callback<()>,
+    pub custom_bird: Option<String>,
+    pub on_custom_bird: Callback<Option<String>>,
+}
+
+#[function_component(SettingsScreen)]
+pub fn settings_screen(props: &SettingsScreenProps) -> Html {
+    let SettingsScreenProps {
+        settings,
+        on_change,
+        on_close,
+        custom_bird,
+        on_custom_bird,
+    } = props;
+    let capturing = use_state(|| None::<Action>);
+    let editing_bird = use_state(|| false);
+    let custom_active = custom_bird.is_some() && settings.skin == CUSTOM_SKIN;
+
+    let update = |f: fn(&mut Settings, bool)| {
+        let settings = settings.clone();
+        let on_change = on_change.clone();
+        Callback::from(move |v: bool| {
+            let mut settings = settings.clone();
+            f(&mut settings, v);
+            on_change.emit(settings);
+        })
+    };
+
+    html! {
+        <div class="screen">
+            <div class="screen-header">
+                <h2>{ tr("Settings") }</h2>
+                <button onclick={on_close.reform(|_| ())}>{ "✕" }</button>
+            </div>
+            <h3>{ tr("Rules") }</h3>
+            <label class="setting">
+                <select onchange={
+                    let settings = settings.clone();
+                    let on_change = on_change.clone();
+                    Callback::from(move |e: yew::Event| {
+                        let id = e.target_unchecked_into::<HtmlSelectElement>().value();
+                        let mut settings = settings.clone();
+                        settings.rules = Rules::ALL
+                            .into_iter()
+                            .find(|rules| rules.id() == id)
+                            .unwrap_or_default();
+                        on_change.emit(settings);
+                    })
+                }>
+                    { for Rules::ALL.iter().map(|rules| html! {
+                        <option value={rules.id()} selected={*rules == settings.rules}>
+                            { tr(rules.name()) }
+                        </option>
+                    }) }
+                </select>
+            </label>
+            <h3>{ tr("Gamepad") }</h3>
+            { checkbox(
+                "Analog triggers control climb rate",
+                settings.analog_triggers,
+                update(|s, v| s.analog_triggers = v),
+            ) }
+            <h3>{ tr("Skin") }</h3>
+            <div class="skins">
+                { for SKINS.iter().map(|skin| {
+                    let onclick = {
+                        let settings = settings.clone();
+                        let on_change = on_change.clone();
+                        Callback::from(move |_| {
+                            let mut settings = settings.clone();
+                            settings.skin = skin.id.to_string();
+                            on_change.emit(settings);
+                        })
+                    };
+                    let active = !custom_active && std::ptr::eq(skin, Skin::by_id(&settings.skin));
+                    html! {
+                        <button class={classes!("skin", active.then_some("active"))} {onclick}>
+                            <img src="static/bird.webp" style={format!("filter: {}", skin.filter)} />
+                            { tr(skin.name) }
+                        </button>
+                    }
+                }) }
+                if let Some(src) = custom_bird.clone() {
+                    <button
+                        class={classes!("skin", custom_active.then_some("active"))}
+                        onclick={
+                            let settings = settings.clone();
+                            let on_change = on_change.clone();
+                            Callback::from(move |_| {
+                                let mut settings = settings.clone();
+                                settings.skin = CUSTOM_SKIN.to_string();
+                                on_change.emit(settings);
+                            })
+                        }
+                    >
+                        <img {src} />
+                        { tr("My bird") }
+                    </button>
+                }
+                <button class="skin" onclick={
+                    let editing_bird = editing_bird.clone();
+                    Callback::from(move |_| editing_bird.set(true))
+                }>
+                    <span class="skin-add">{ "+" }</span>
+                    { tr("Upload") }
+                </button>
+            </div>
+            { checkbox(
+                "Low quality graphics",
+                settings.low_quality,
+                update(|s, v| s.low_quality = v),
+            ) }
+            <h3>{ tr("Language") }</h3>
+            <label class="setting">
+                <select onchange={
+                    let settings = settings.clone();
+                    let on_change = on_change.clone();
+                    Callback::from(move |e: yew::Event| {
+                        let id = e.target_unchecked_into::<HtmlSelectElement>().value();
+                        let mut settings = settings.clone();
+                        settings.language = Language::ALL
+                            .into_iter()
+                            .find(|language| language.id() == id)
+                            .unwrap_or_default();
+                        on_change.emit(settings);
+                    })
+                }>
+                    { for Language::ALL.iter().map(|language| html! {
+                        <option value={language.id()} selected={*language == settings.language}>
+                            { language.name() }
+                        </option>
+                    }) }
+                </select>
+            </label>
+            { checkbox(
+                "Show distances in feet",
+                settings.imperial,
+                update(|s, v| s.imperial = v),
+            ) }
+            <h3>{ tr("Sound") }</h3>
+            { checkbox("Mute", settings.muted, update(|s, v| s.muted = v)) }
+            <h3>{ tr("Accessibility") }</h3>
+            <label class="setting">
+                { tr("Game speed ") }
+                <select onchange={
+                    let settings = settings.clone();
+                    let on_change = on_change.clone();
+                    Callback::from(move |e: yew::Event| {
+                        let id = e.target_unchecked_into::<HtmlSelectElement>().value();
+                        let mut settings = settings.clone();
+                        settings.speed = GameSpeed::ALL
+                            .into_iter()
+                            .find(|speed| speed.id() == id)
+                            .unwrap_or_default();
+                        on_change.emit(settings);
+                    })
+                }>
+                    { for GameSpeed::ALL.iter().map(|speed| html! {
+                        <option value={speed.id()} selected={*speed == settings.speed}>
+                            { speed.name() }
+                        </option>
+                    }) }
+                </select>
+            </label>
+            { checkbox(
+                "Reduced motion",
+                settings.reduced_motion,
+                update(|s, v| s.reduced_motion = v),
+            ) }
+            { checkbox(
+                "High contrast",
+                settings.high_contrast,
+                update(|s, v| s.high_contrast = v),
+            ) }
+            { checkbox(
+                "Beep before obstacles",
+                settings.obstacle_cues,
+                update(|s, v| s.obstacle_cues = v),
+            ) }
+            { checkbox(
+                "Press once to start climbing, again to stop",
+                settings.toggle_fly,
+                update(|s, v| s.toggle_fly = v),
+            ) }
+            <h3>{ tr("Controls") }</h3>
+            <table class="bindings">
+                { for Action::ALL.iter().map(|&action| html! {
+                    <tr>
+                        <td>{ action.name() }</td>
+                        <td>
+                            { for settings.bindings.get(action).iter().map(|binding| {
+                                let onclick = {
+                                    let settings = settings.clone();
+                                    let on_change = on_change.clone();
+                                    let binding = binding.clone();
+                                    Callback::from(move |_| {
+                                        let mut settings = settings.clone();
+                                        settings.bindings.unbind(action, &binding);
+                                        on_change.emit(settings);
+                                    })
+                                };
+                                html! {
+                                    <span class="binding">
+                                        { binding.label() }
+                                        <button {onclick}>{ "✕" }</button>
+                                    </span>
+                                }
+                            }) }
+                            <button class="binding-add" onclick={
+                                let capturing = capturing.clone();
+                                Callback::from(move |_| capturing.set(Some(action)))
+                            }>
+                                { "+" }
+                            </button>
+                        </td>
+                    </tr>
+                }) }
+            </table>
+            <button onclick={
+                let settings = settings.clone();
+                let on_change = on_change.clone();
+                Callback::from(move |_| {
+                    let mut settings = settings.clone();
+                    settings.bindings = Bindings::default();
+                    on_change.emit(settings);
+                })
+            }>
+                { tr("Reset controls") }
+            </button>
+            if let Some(action) = *capturing {
+                <Capture
+                    {action}
+                    on_capture={
+                        let settings = settings.clone();
+                        let on_change = on_change.clone();
+                        let capturing = capturing.clone();
+                        Callback::from(move |binding: Binding| {
+                            let mut settings = settings.clone();
+                            settings.bindings.bind(action, binding);
+                            on_change.emit(settings);
+                            capturing.set(None);
+                        })
+                    }
+                    on_cancel={
+                        let capturing = capturing.clone();
+                        Callback::from(move |_| capturing.set(None))
+                    }
+                />
+            }
+            if *editing_bird {
+                <CustomBird
+                    current={custom_bird.clone()}
+                    on_save={
+                        let on_custom_bird = on_custom_bird.clone();
+                        let editing_bird = editing_bird.clone();
+                        Callback::from(move |bird| {
+                            on_custom_bird.emit(bird);
+                            editing_bird.set(false);
+                        })
+                    }
+                    on_close={
+                        let editing_bird = editing_bird.clone();
+                        Callback::from(move |_| editing_bird.set(false))
+                    }
+                />
+            }
+        </div>
+    }
+}
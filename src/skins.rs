@@ -0,0 +1,79 @@
+use flying_bird_sim::theme::MAX_FRAMES;
+use flying_bird_sim::BIRD_SIZE;
+use wasm_bindgen::JsCast;
+use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+pub use flying_bird_sim::theme::{Anim, Animator, Skin, SKINS};
+
+// 一行一种动作，从一张鸟的图按皮肤染色后画出来
+pub struct SpriteSheet {
+    canvas: HtmlCanvasElement,
+    // 每帧的边长，和原图一样大
+    size: f64,
+}
+
+impl SpriteSheet {
+    pub fn build(img: &HtmlImageElement, skin: &Skin) -> Self {
+        let size = img.natural_width().max(1) as f64;
+        let canvas: HtmlCanvasElement = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("canvas")
+            .unwrap()
+            .unchecked_into();
+        canvas.set_width((size * MAX_FRAMES as f64) as u32);
+        canvas.set_height((size * Anim::ALL.len() as f64) as u32);
+        let ctx: CanvasRenderingContext2d =
+            canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+        ctx.set_filter(skin.filter);
+
+        for anim in Anim::ALL {
+            for i in 0..anim.frames() {
+                let (sx, sy, rotate) = anim.pose(i);
+                ctx.save();
+                ctx.translate((i as f64 + 0.5) * size, (anim.row() as f64 + 0.5) * size)
+                    .unwrap();
+                ctx.rotate(rotate).unwrap();
+                ctx.scale(sx, sy).unwrap();
+                ctx.draw_image_with_html_image_element_and_dw_and_dh(
+                    img,
+                    -size / 2.,
+                    -size / 2.,
+                    size,
+                    size,
+                )
+                .unwrap();
+                ctx.restore();
+            }
+        }
+
+        Self { canvas, size }
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        (anim, frame): (Anim, usize),
+        x: f64,
+        y: f64,
+        angle: f64,
+    ) {
+        ctx.save();
+        ctx.translate(x, y).unwrap();
+        ctx.rotate(angle).unwrap();
+        ctx.draw_image_with_html_canvas_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            &self.canvas,
+            frame as f64 * self.size,
+            anim.row() as f64 * self.size,
+            self.size,
+            self.size,
+            -BIRD_SIZE / 2.,
+            -BIRD_SIZE / 2.,
+            BIRD_SIZE,
+            BIRD_SIZE,
+        )
+        .expect("draw bird failed");
+        ctx.restore();
+    }
+}
@@ -0,0 +1,244 @@
+use flying_bird_sim::CrashCause;
+use js_sys::Date;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::{function_component, html, use_effect_with, use_node_ref, Callback, Html, Properties};
+
+use crate::download::download_text;
+use crate::i18n::{format_distance, format_number, tr, trf};
+use crate::storage::{load_json, save_json};
+use crate::INTERV;
+
+const STORAGE_KEY: &str = "runs";
+const MAX_RUNS: usize = 10000;
+
+const CHART_W: f64 = 600.;
+const CHART_H: f64 = 200.;
+const CHART_PADDING: f64 = 30.;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    // 结束时间，ms
+    pub time: f64,
+    pub score: u32,
+    #[serde(default)]
+    pub style_score: u32,
+    // 单位m
+    pub distance: f64,
+    pub airborne_ms: u32,
+    pub cause: CrashCause,
+}
+
+pub fn load_runs() -> Vec<RunRecord> {
+    load_json(STORAGE_KEY)
+}
+
+pub fn add_run(record: RunRecord) {
+    let mut runs = load_runs();
+    runs.push(record);
+    if runs.len() > MAX_RUNS {
+        runs.drain(..runs.len() - MAX_RUNS);
+    }
+    save_json(STORAGE_KEY, &runs);
+}
+
+// 当前这一局的累计数据
+#[derive(Default)]
+pub struct RunTracker {
+    distance: f64,
+    steps: u32,
+}
+
+impl RunTracker {
+    pub fn step(&mut self, xl: f64) {
+        self.distance += xl / 100.0;
+        self.steps += 1;
+    }
+
+    pub fn finish(&self, score: u32, style_score: u32, cause: CrashCause) -> RunRecord {
+        RunRecord {
+            time: Date::now(),
+            score,
+            style_score,
+            distance: self.distance,
+            airborne_ms: self.steps * INTERV,
+            cause,
+        }
+    }
+}
+
+struct Summary {
+    flights: usize,
+    crashes: [usize; 4],
+    total_distance: f64,
+    total_airborne_ms: u64,
+    average: f64,
+    median: f64,
+}
+
+impl Summary {
+    fn from_runs(runs: &[RunRecord]) -> Self {
+        let mut scores: Vec<u32> = runs.iter().map(|r| r.score).collect();
+        scores.sort_unstable();
+        let median = match scores.len() {
+            0 => 0.0,
+            n if n % 2 == 1 => scores[n / 2] as f64,
+            n => (scores[n / 2 - 1] + scores[n / 2]) as f64 / 2.0,
+        };
+
+        Self {
+            flights: runs.len(),
+            crashes: CrashCause::ALL.map(|c| runs.iter().filter(|r| r.cause == c).count()),
+            total_distance: runs.iter().map(|r| r.distance).sum(),
+            total_airborne_ms: runs.iter().map(|r| r.airborne_ms as u64).sum(),
+            average: scores.iter().sum::<u32>() as f64 / scores.len().max(1) as f64,
+            median,
+        }
+    }
+}
+
+fn format_duration(ms: u64) -> String {
+    let s = ms / 1000;
+    format!("{}:{:0>2}:{:0>2}", s / 3600, s / 60 % 60, s % 60)
+}
+
+fn to_csv(runs: &[RunRecord]) -> String {
+    let mut csv = String::from("time,score,style_score,distance_m,airborne_ms,cause\n");
+    for r in runs {
+        csv += &format!(
+            "{},{},{},{:.2},{},{}\n",
+            String::from(Date::new(&JsValue::from_f64(r.time)).to_iso_string()),
+            r.score,
+            r.style_score,
+            r.distance,
+            r.airborne_ms,
+            r.cause.id()
+        );
+    }
+    csv
+}
+
+fn chart_ctx(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
+    canvas.set_width(CHART_W as u32);
+    canvas.set_height(CHART_H as u32);
+    let ctx =
+        CanvasRenderingContext2d::from(JsValue::from(canvas.get_context("2d").unwrap().unwrap()));
+    ctx.clear_rect(0., 0., CHART_W, CHART_H);
+    ctx.set_font("12px sans-serif");
+    ctx
+}
+
+fn draw_score_history(canvas: &HtmlCanvasElement, runs: &[RunRecord]) {
+    let ctx = chart_ctx(canvas);
+    let max_score = runs.iter().map(|r| r.score).max().unwrap_or(0).max(1) as f64;
+    let (x0, y0) = (CHART_PADDING, CHART_H - CHART_PADDING);
+    let (cw, ch) = (CHART_W - 2.0 * CHART_PADDING, CHART_H - 2.0 * CHART_PADDING);
+
+    ctx.set_stroke_style_str("rgb(190, 190, 190)");
+    ctx.begin_path();
+    ctx.move_to(x0, y0 - ch);
+    ctx.line_to(x0, y0);
+    ctx.line_to(x0 + cw, y0);
+    ctx.stroke();
+
+    ctx.set_fill_style_str("rgb(100, 100, 100)");
+    ctx.fill_text(&max_score.to_string(), 2., y0 - ch + 4.)
+        .unwrap();
+    ctx.fill_text("0", 2., y0 + 4.).unwrap();
+    ctx.fill_text(&runs.len().to_string(), x0 + cw - 10., y0 + 18.)
+        .unwrap();
+
+    let step = cw / (runs.len().max(2) - 1) as f64;
+    ctx.set_stroke_style_str("rgb(100, 100, 100)");
+    ctx.begin_path();
+    for (i, r) in runs.iter().enumerate() {
+        ctx.line_to(x0 + i as f64 * step, y0 - r.score as f64 / max_score * ch);
+    }
+    ctx.stroke();
+}
+
+fn draw_crash_causes(canvas: &HtmlCanvasElement, crashes: &[usize; 4]) {
+    let ctx = chart_ctx(canvas);
+    let max_cnt = crashes.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let label_w = 90.;
+    let bar_h = (CHART_H - 2.0 * CHART_PADDING) / 4.0;
+    for (i, (cause, cnt)) in CrashCause::ALL.iter().zip(crashes).enumerate() {
+        let y = CHART_PADDING + i as f64 * bar_h;
+        ctx.set_fill_style_str("rgb(100, 100, 100)");
+        ctx.fill_text(tr(cause.name()), 0., y + bar_h / 2. + 4.)
+            .unwrap();
+        let bar_w = *cnt as f64 / max_cnt * (CHART_W - label_w - 60.);
+        ctx.fill_rect(label_w, y + 4., bar_w, bar_h - 8.);
+        ctx.fill_text(&cnt.to_string(), label_w + bar_w + 6., y + bar_h / 2. + 4.)
+            .unwrap();
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct StatsScreenProps {
+    pub on_close: Callback<()>,
+}
+
+#[function_component(StatsScreen)]
+pub fn stats_screen(props: &StatsScreenProps) -> Html {
+    let runs = load_runs();
+    let summary = Summary::from_runs(&runs);
+    let history_ref = use_node_ref();
+    let causes_ref = use_node_ref();
+
+    {
+        let runs = runs.clone();
+        let crashes = summary.crashes;
+        use_effect_with(
+            (history_ref.clone(), causes_ref.clone()),
+            move |(history_ref, causes_ref)| {
+                if let Some(canvas) = history_ref.cast::<HtmlCanvasElement>() {
+                    draw_score_history(&canvas, &runs);
+                }
+                if let Some(canvas) = causes_ref.cast::<HtmlCanvasElement>() {
+                    draw_crash_causes(&canvas, &crashes);
+                }
+            },
+        );
+    }
+
+    let export_csv = {
+        let runs = runs.clone();
+        Callback::from(move |_| download_text("flying-bird-runs.csv", "text/csv", &to_csv(&runs)))
+    };
+    let export_json = Callback::from(move |_| {
+        download_text(
+            "flying-bird-runs.json",
+            "application/json",
+            &serde_json::to_string_pretty(&runs).unwrap(),
+        )
+    });
+
+    html! {
+        <div class="screen">
+            <div class="screen-header">
+                <h2>{ tr("Statistics") }</h2>
+                <button onclick={props.on_close.reform(|_| ())}>{ "✕" }</button>
+            </div>
+            <table class="stats">
+                <tr><td>{ tr("Flights") }</td><td>{ summary.flights }</td></tr>
+                { for CrashCause::ALL.iter().zip(summary.crashes).map(|(cause, cnt)| html! {
+                    <tr><td>{ trf("Crashes into {cause}", &[("cause", &tr(cause.name()))]) }</td><td>{ cnt }</td></tr>
+                }) }
+                <tr><td>{ tr("Total distance") }</td><td>{ format_distance(summary.total_distance) }</td></tr>
+                <tr><td>{ tr("Time airborne") }</td><td>{ format_duration(summary.total_airborne_ms) }</td></tr>
+                <tr><td>{ tr("Average score") }</td><td>{ format_number((summary.average * 10.).round() / 10.) }</td></tr>
+                <tr><td>{ tr("Median score") }</td><td>{ format_number((summary.median * 10.).round() / 10.) }</td></tr>
+            </table>
+            <h3>{ tr("Score history") }</h3>
+            <canvas class="chart" ref={history_ref} />
+            <h3>{ tr("Crashes") }</h3>
+            <canvas class="chart" ref={causes_ref} />
+            <div class="export">
+                <button onclick={export_csv}>{ tr("Export CSV") }</button>
+                <button onclick={export_json}>{ tr("Export JSON") }</button>
+            </div>
+        </div>
+    }
+}
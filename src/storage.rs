@@ -0,0 +1,20 @@
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::{window, Storage};
+
+fn local_storage() -> Storage {
+    window().unwrap().local_storage().unwrap().unwrap()
+}
+
+pub fn load_json<T: DeserializeOwned + Default>(key: &str) -> T {
+    local_storage()
+        .get_item(key)
+        .unwrap()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_json<T: Serialize>(key: &str, value: &T) {
+    local_storage()
+        .set_item(key, &serde_json::to_string(value).unwrap())
+        .unwrap();
+}
@@ -0,0 +1,92 @@
+use flying_bird_sim::{CHECK_SIZE, OB_WIDTH};
+use web_sys::CanvasRenderingContext2d;
+
+// 离障碍物边缘不到这个距离就算擦边
+const NEAR_MISS_MARGIN: f64 = 30.;
+const POPUP_STEPS: u32 = 90;
+const POPUP_RISE: f64 = 0.6;
+const POPUP_COLOR: &str = "rgb(230, 120, 60)";
+
+struct Popup {
+    // 世界坐标
+    x: f64,
+    y: f64,
+    text: String,
+    age: u32,
+}
+
+#[derive(Default)]
+pub struct StyleTracker {
+    // 当前障碍物里最小的间隙
+    tightest: Option<f64>,
+    combo: u32,
+    popups: Vec<Popup>,
+}
+
+impl StyleTracker {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    // 在障碍物里每一帧记录离上下边缘的距离
+    pub fn observe(&mut self, pos_y: f64, y1: f64, y2: f64) {
+        let clearance = (pos_y - y1).min(y2 - pos_y) - CHECK_SIZE;
+        self.tightest = Some(self.tightest.map_or(clearance, |t| t.min(clearance)));
+    }
+
+    // 通过障碍物时结算，返回加分
+    pub fn pass(&mut self, ob_x: f64, y1: f64, y2: f64, pos_y: f64) -> u32 {
+        let Some(clearance) = self.tightest.take() else {
+            return 0;
+        };
+        if clearance >= NEAR_MISS_MARGIN {
+            self.combo = 0;
+            return 0;
+        }
+
+        self.combo += 1;
+        let base = 1 + ((NEAR_MISS_MARGIN - clearance) / 10.0) as u32;
+        let points = base * self.combo;
+
+        let y = if pos_y - y1 < y2 - pos_y {
+            y1 + 40.
+        } else {
+            y2 - 40.
+        };
+        self.popups.push(Popup {
+            x: ob_x + OB_WIDTH / 2.,
+            y,
+            text: if self.combo > 1 {
+                format!("+{points} x{}", self.combo)
+            } else {
+                format!("+{points}")
+            },
+            age: 0,
+        });
+        points
+    }
+
+    // reduced_motion时文字停在原地慢慢消失
+    pub fn step(&mut self, reduced_motion: bool) {
+        for p in self.popups.iter_mut() {
+            if !reduced_motion {
+                p.y -= POPUP_RISE;
+            }
+            p.age += 1;
+        }
+        self.popups.retain(|p| p.age < POPUP_STEPS);
+    }
+
+    // dx: 世界坐标到屏幕坐标的偏移
+    pub fn draw(&self, ctx: &CanvasRenderingContext2d, dx: f64) {
+        ctx.save();
+        ctx.set_font("bold 40px sans-serif");
+        ctx.set_text_align("center");
+        ctx.set_fill_style_str(POPUP_COLOR);
+        for p in self.popups.iter() {
+            ctx.set_global_alpha(1.0 - p.age as f64 / POPUP_STEPS as f64);
+            ctx.fill_text(&p.text, p.x + dx, p.y).unwrap();
+        }
+        ctx.restore();
+    }
+}
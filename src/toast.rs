@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use js_sys::Date;
+use yew::{html, Html, Reducible};
+
+const TOAST_TIME: f64 = 3000.;
+
+#[derive(Clone, PartialEq)]
+struct Toast {
+    id: u32,
+    text: String,
+    expires_at: f64,
+}
+
+#[derive(Default, PartialEq)]
+pub struct Toasts {
+    next_id: u32,
+    list: Vec<Toast>,
+}
+
+pub enum ToastAction {
+    Push(String),
+    Expire,
+}
+
+impl Reducible for Toasts {
+    type Action = ToastAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let now = Date::now();
+        match action {
+            ToastAction::Push(text) => {
+                let mut list = self.list.clone();
+                list.push(Toast {
+                    id: self.next_id,
+                    text,
+                    expires_at: now + TOAST_TIME,
+                });
+                Rc::new(Self {
+                    next_id: self.next_id + 1,
+                    list,
+                })
+            }
+            ToastAction::Expire => {
+                if self.list.iter().all(|t| t.expires_at > now) {
+                    return self;
+                }
+                Rc::new(Self {
+                    next_id: self.next_id,
+                    list: self
+                        .list
+                        .iter()
+                        .filter(|t| t.expires_at > now)
+                        .cloned()
+                        .collect(),
+                })
+            }
+        }
+    }
+}
+
+impl Toasts {
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn view(&self) -> Html {
+        html! {
+            <div id="toasts" class="no-select">
+                { for self.list.iter().map(|t| html! {
+                    <div key={t.id} class="toast">{ &t.text }</div>
+                }) }
+            </div>
+        }
+    }
+}